@@ -8,7 +8,7 @@ fn gfa_normal(filename: &str) {
 }
 
 /// Check get_node function wit nondigit
-fn id_non_digit(ff: &Vec<Segment<u64, ()>>, gfa: &Gfa<u64, (), ()>) {
+fn id_non_digit(ff: &[Segment<u64, ()>], gfa: &Gfa<u64, (), ()>) {
     for x in ff.iter() {
         let _ = gfa.get_segment_nondigit(&x.id);
         let _b = gfa.get_sequence_by_id_nondigit(&x.id);
@@ -16,7 +16,7 @@ fn id_non_digit(ff: &Vec<Segment<u64, ()>>, gfa: &Gfa<u64, (), ()>) {
 }
 
 /// Check get_node function wit digit
-fn id_digit(ff: &Vec<Segment<u64, ()>>, gfa: &Gfa<u64, (), ()>) {
+fn id_digit(ff: &[Segment<u64, ()>], gfa: &Gfa<u64, (), ()>) {
     for x in ff.iter() {
         let _ = gfa.get_segment_digit(&x.id);
         let _b = gfa.get_sequence_by_digit(&x.id);
@@ -1,33 +1,76 @@
 mod logging;
 
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs::File;
+use std::hash::Hash;
 use std::io::{prelude::*, BufReader, SeekFrom};
 
-use log::info;
+use log::{debug, info, trace};
+#[cfg(feature = "shuffle")]
 use rand::prelude::SliceRandom;
 use std::path::Path as file_path;
 
 #[derive(Debug, Clone, Default, Ord, PartialEq, Eq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// GFA header line
 /// This line begins with an 'H'
+///
+/// tag, typ and version_number are taken from the VN tag, all tags are stored in tags
 pub struct Header {
     pub tag: String,
     pub typ: String,
     pub version_number: String,
+    /// All tags of all header lines (tag, type, value)
+    pub tags: Vec<(String, char, String)>,
 }
 
 impl Header {
-    /// Parse header from string (H-line)
-    fn from_string(line: &str) -> Header {
-        let line = line.split_whitespace().nth(1).unwrap();
-        let tag = line.split(':').nth(0).unwrap().to_string();
-        let typ = line.split(':').nth(1).unwrap().to_string();
-        let version_number = line.split(':').nth(2).unwrap().to_string();
-        Header {
-            tag,
-            typ,
-            version_number,
+    /// Add the tags of a header line (fields after the H)
+    ///
+    /// Malformed tags are ignored
+    fn add_fields<'a>(&mut self, fields: impl Iterator<Item = &'a str>) {
+        for field in fields {
+            let mut parts = field.splitn(3, ':');
+            let (tag, typ, value) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(tag), Some(typ), Some(value)) if typ.len() == 1 => (tag, typ, value),
+                _ => continue,
+            };
+            if tag == "VN" {
+                self.tag = tag.to_string();
+                self.typ = typ.to_string();
+                self.version_number = value.to_string();
+            }
+            self.tags.push((
+                tag.to_string(),
+                typ.chars().next().unwrap(),
+                value.to_string(),
+            ));
+        }
+    }
+
+    /// Value of the first header tag with this name (e.g. "RS")
+    pub fn get(&self, tag: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(name, _, _)| name == tag)
+            .map(|(_, _, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for Header {
+    /// A single H line with all tags (without newline)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "H")?;
+        if self.tags.is_empty() && !self.version_number.is_empty() {
+            write!(f, "\t{}:{}:{}", self.tag, self.typ, self.version_number)?;
         }
+        for (tag, typ, value) in self.tags.iter() {
+            write!(f, "\t{}:{}:{}", tag, typ, value)?;
+        }
+        Ok(())
     }
 }
 
@@ -38,14 +81,61 @@ pub trait SampleType {
     /// Might use a String to add the relevant data
     fn parse1(input: &str, s: &mut String) -> Self;
 
+    /// Parse an id while reading a file
+    ///
+    /// Only interned ids (InternedId) use the interner, all others call parse1
+    fn parse_id(input: &str, s: &mut String, _interner: &mut Interner) -> Self
+    where
+        Self: Sized,
+    {
+        Self::parse1(input, s)
+    }
+
     fn get_usize(&self) -> usize;
 
     fn is_digit() -> bool;
+
+    /// The id as written in the file
+    ///
+    /// s is the graph sequence, interner the graph interner (see Gfa::original_id)
+    fn id_string(&self, _s: &str, _interner: &Interner) -> String {
+        self.get_usize().to_string()
+    }
+
+    /// Move the id by offset (only needed if the id points into the graph sequence)
+    fn adjust(&mut self, _offset: usize) {}
+
+    /// Parse an id while reading a file, None if it is not valid for this type
+    ///
+    /// Only numeric ids can be invalid (e.g. "a1" for u32). If None is returned, s and interner must
+    /// not be changed, so that a skipped line leaves no traces in the graph.
+    fn try_parse_id(input: &str, s: &mut String, interner: &mut Interner) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(Self::parse_id(input, s, interner))
+    }
+
+    /// Parse an id without adding it to the graph (None if an interned id is unknown)
+    fn find_id(input: &str, s: &mut String, _interner: &Interner) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        Some(Self::parse1(input, s))
+    }
+
+    /// Copy the id from graph sequence old to graph sequence new (used for subgraphs)
+    fn relocate(&self, _old: &str, _new: &mut String) -> Self
+    where
+        Self: Clone,
+    {
+        self.clone()
+    }
 }
 
 impl SampleType for String {
-    fn parse1(_input: &str, s: &mut String) -> Self {
-        s.to_string()
+    fn parse1(input: &str, _s: &mut String) -> Self {
+        input.to_string()
     }
 
     fn get_usize(&self) -> usize {
@@ -55,12 +145,20 @@ impl SampleType for String {
     fn is_digit() -> bool {
         false
     }
+
+    fn id_string(&self, _s: &str, _interner: &Interner) -> String {
+        self.clone()
+    }
 }
 
 impl SampleType for usize {
     fn parse1(input: &str, _s: &mut String) -> Self {
         input.parse().unwrap()
     }
+
+    fn try_parse_id(input: &str, _s: &mut String, _interner: &mut Interner) -> Option<Self> {
+        input.parse().ok()
+    }
     fn get_usize(&self) -> usize {
         *self
     }
@@ -74,6 +172,10 @@ impl SampleType for u64 {
     fn parse1(input: &str, _s: &mut String) -> Self {
         input.parse().unwrap()
     }
+
+    fn try_parse_id(input: &str, _s: &mut String, _interner: &mut Interner) -> Option<Self> {
+        input.parse().ok()
+    }
     fn get_usize(&self) -> usize {
         *self as usize
     }
@@ -87,6 +189,10 @@ impl SampleType for u32 {
         input.parse().unwrap()
     }
 
+    fn try_parse_id(input: &str, _s: &mut String, _interner: &mut Interner) -> Option<Self> {
+        input.parse().ok()
+    }
+
     fn get_usize(&self) -> usize {
         *self as usize
     }
@@ -96,6 +202,52 @@ impl SampleType for u32 {
     }
 }
 
+/// Signed ids must not be negative, they are rejected while parsing (get_usize panics on them)
+impl SampleType for i64 {
+    fn parse1(input: &str, _s: &mut String) -> Self {
+        input.parse().unwrap()
+    }
+
+    fn try_parse_id(input: &str, _s: &mut String, _interner: &mut Interner) -> Option<Self> {
+        input.parse().ok().filter(|x: &Self| *x >= 0)
+    }
+
+    fn get_usize(&self) -> usize {
+        usize::try_from(*self).expect("ERROR: NEGATIVE SEGMENT ID")
+    }
+
+    fn is_digit() -> bool {
+        true
+    }
+
+    fn id_string(&self, _s: &str, _interner: &Interner) -> String {
+        self.to_string()
+    }
+}
+
+/// Signed ids must not be negative, they are rejected while parsing (get_usize panics on them)
+impl SampleType for i32 {
+    fn parse1(input: &str, _s: &mut String) -> Self {
+        input.parse().unwrap()
+    }
+
+    fn try_parse_id(input: &str, _s: &mut String, _interner: &mut Interner) -> Option<Self> {
+        input.parse().ok().filter(|x: &Self| *x >= 0)
+    }
+
+    fn get_usize(&self) -> usize {
+        usize::try_from(*self).expect("ERROR: NEGATIVE SEGMENT ID")
+    }
+
+    fn is_digit() -> bool {
+        true
+    }
+
+    fn id_string(&self, _s: &str, _interner: &Interner) -> String {
+        self.to_string()
+    }
+}
+
 impl SampleType for SeqIndex {
     fn parse1(input: &str, s: &mut String) -> Self {
         s.push_str(input);
@@ -109,6 +261,96 @@ impl SampleType for SeqIndex {
     fn is_digit() -> bool {
         false
     }
+
+    fn id_string(&self, s: &str, _interner: &Interner) -> String {
+        self.get_string(s).to_string()
+    }
+
+    fn adjust(&mut self, offset: usize) {
+        self.0[0] += offset;
+        self.0[1] += offset
+    }
+
+    fn relocate(&self, old: &str, new: &mut String) -> Self {
+        SeqIndex::parse1(self.get_string(old), new)
+    }
+}
+
+/// Dense number for a string id (see Interner)
+///
+/// Allows O(1) lookups (like numeric ids) for graphs with string ids.
+/// Only usable with the single-threaded parsers, since the numbers depend on the parsing order.
+///
+/// Memory size: 4 byte
+#[derive(Debug, Clone, Copy, Ord, Eq, PartialOrd, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InternedId(u32);
+
+impl SampleType for InternedId {
+    fn parse1(_input: &str, _s: &mut String) -> Self {
+        panic!("ERROR: InternedId needs an interner, use Gfa::intern")
+    }
+
+    fn parse_id(input: &str, _s: &mut String, interner: &mut Interner) -> Self {
+        interner.intern(input)
+    }
+
+    fn find_id(input: &str, _s: &mut String, interner: &Interner) -> Option<Self> {
+        interner.get(input)
+    }
+
+    fn get_usize(&self) -> usize {
+        self.0 as usize
+    }
+
+    fn is_digit() -> bool {
+        true
+    }
+
+    fn id_string(&self, _s: &str, interner: &Interner) -> String {
+        interner.name(self).unwrap_or_default().to_string()
+    }
+}
+
+/// Maps string ids to InternedId (and back)
+///
+/// Numbers are given in order of appearance, starting at 0
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Interner {
+    ids: HashMap<String, u32>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    /// Get the id of a name, adding the name if it is new
+    pub fn intern(&mut self, name: &str) -> InternedId {
+        if let Some(id) = self.ids.get(name) {
+            return InternedId(*id);
+        }
+        let id = self.names.len() as u32;
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        InternedId(id)
+    }
+
+    /// Get the id of a name
+    pub fn get(&self, name: &str) -> Option<InternedId> {
+        self.ids.get(name).map(|x| InternedId(*x))
+    }
+
+    /// Get the original name of an id
+    pub fn name(&self, id: &InternedId) -> Option<&str> {
+        self.names.get(id.0 as usize).map(|x| x.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
 }
 
 /// Optional fields
@@ -118,6 +360,19 @@ pub trait Opt {
     fn parse1(input: Option<&str>, s: &mut String) -> Self;
 
     fn adjust(&mut self, _offset: usize) {}
+
+    /// Raw text (s is the graph sequence), empty if not stored
+    fn as_str<'a>(&self, _s: &'a str) -> &'a str {
+        ""
+    }
+
+    /// Copy the data from graph sequence old to graph sequence new (used for subgraphs)
+    fn relocate(&self, _old: &str, _new: &mut String) -> Self
+    where
+        Self: Clone,
+    {
+        self.clone()
+    }
 }
 
 impl Opt for () {
@@ -128,18 +383,27 @@ impl Opt for () {
 
 impl Opt for SeqIndex {
     fn parse1(input: Option<&str>, s: &mut String) -> Self {
-        if input.is_none() {
-            SeqIndex([0, 0])
-        } else {
-            let input = input.unwrap();
-            s.push_str(input);
-            Self([s.len() - input.len(), s.len()])
+        match input {
+            // Empty, but at the current end (stays consistent when graphs are appended)
+            None => SeqIndex([s.len(), s.len()]),
+            Some(input) => {
+                s.push_str(input);
+                Self([s.len() - input.len(), s.len()])
+            }
         }
     }
 
     fn adjust(&mut self, offset: usize) {
         self.0[0] += offset;
-        self.0[0] += offset
+        self.0[1] += offset
+    }
+
+    fn as_str<'a>(&self, s: &'a str) -> &'a str {
+        self.get_string(s)
+    }
+
+    fn relocate(&self, old: &str, new: &mut String) -> Self {
+        SeqIndex::parse1(self.get_string(old), new)
     }
 }
 
@@ -150,6 +414,7 @@ impl Opt for SeqIndex {
 ///
 /// Memory size: 16 byte
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SeqIndex([usize; 2]);
 
 impl SeqIndex {
@@ -174,13 +439,60 @@ impl SeqIndex {
 
 /// GFA segment
 ///
-/// Memory size: 16 + 4 + 0 + 0 = 20
+/// Memory size: 16 + 4 + 0 + 0 + 16 = 36
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Segment<T: SampleType + Ord, S: Opt + Ord> {
     pub id: T,
     pub sequence: SeqIndex,
     pub length: u32,
     pub opt: S,
+    /// Sequence stored in the segment itself (ParseOptions::store_sequences_inline)
+    pub inline_sequence: Option<Box<str>>,
+}
+
+impl<T: SampleType + Ord, S: Opt + Ord> Segment<T, S> {
+    /// Sequence of the segment
+    ///
+    /// seq is the sequence of the graph (Gfa::get_sequence), not needed for inline sequences
+    pub fn sequence_str<'a>(&'a self, seq: &'a str) -> &'a str {
+        match &self.inline_sequence {
+            Some(sequence) => sequence,
+            None => self.sequence.get_string(seq),
+        }
+    }
+
+    /// Owned copy of the sequence, independent of the graph
+    pub fn owned_sequence(&self, seq: &str) -> String {
+        self.sequence_str(seq).to_owned()
+    }
+
+    /// The segment has bases stored, in the graph sequence or inline (false if parsed without
+    /// sequences or for "*")
+    ///
+    /// The length is available in any case
+    pub fn has_sequence(&self) -> bool {
+        match &self.inline_sequence {
+            Some(sequence) => !sequence.is_empty(),
+            None => self.sequence.get_len() > 0,
+        }
+    }
+
+    /// Fraction of G and C among the A, C, G and T (case-insensitive)
+    ///
+    /// N and other ambiguity codes are not counted. 0.0 if there are no such bases.
+    pub fn gc_content(&self, seq: &str) -> f32 {
+        let (gc, acgt) = gc_counts(self.sequence_str(seq));
+        gc_fraction(gc, acgt)
+    }
+}
+
+/// Segment bound to the sequence of its graph (see Gfa::segment_view)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentView<'a, T> {
+    pub id: &'a T,
+    pub sequence: &'a str,
+    pub length: u32,
 }
 
 /// GFA link
@@ -188,6 +500,7 @@ pub struct Segment<T: SampleType + Ord, S: Opt + Ord> {
 /// Memory size (u32): 4 + 1 + 4 + 1 + 0 + 0 = 12 (padding)
 ///
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Link<T: SampleType, S: Opt, U: Opt> {
     pub from: T,
     pub to: T,
@@ -201,6 +514,7 @@ pub struct Link<T: SampleType, S: Opt, U: Opt> {
 ///
 /// Memory size (u32): String + 4*X + 1*X + 0 + 0 ~ 5*x
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Path<T: SampleType, S: Opt, U: Opt> {
     pub name: String,
     pub dir: Vec<bool>,
@@ -213,6 +527,7 @@ pub struct Path<T: SampleType, S: Opt, U: Opt> {
 ///
 /// Memory size (u32): 5*x
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Walk<T: SampleType, S: Opt> {
     pub sample_id: String,
     pub hap_index: u32,
@@ -226,6 +541,7 @@ pub struct Walk<T: SampleType, S: Opt> {
 
 /// GFA Containment
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Containment<T: SampleType, S: Opt> {
     pub container: T,
     pub container_dir: bool,
@@ -238,17 +554,319 @@ pub struct Containment<T: SampleType, S: Opt> {
 
 /// GFA Jump
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Jump<T: SampleType, S: Opt> {
+    pub from: T,
+    pub from_dir: bool,
+    pub to: T,
+    pub to_dir: bool,
+    /// Distance between the segments, None if unknown ("*")
+    pub distance: Option<i64>,
+    pub opt: S,
+}
+
+/// GFA2 position on a segment
+///
+/// A trailing '$' marks the end of the segment
+#[derive(Debug, Clone, Copy, Default, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub pos: u64,
+    pub is_end: bool,
+}
+
+impl Position {
+    /// Parse a position (e.g. "10" or "10$")
+    fn parse(s: &str) -> Result<Position, GfaError> {
+        Ok(match s.strip_suffix('$') {
+            Some(pos) => Position {
+                pos: parse_number(pos)?,
+                is_end: true,
+            },
+            None => Position {
+                pos: parse_number(s)?,
+                is_end: false,
+            },
+        })
+    }
+}
+
+/// GFA2 Edge
+///
+/// Only parsed in GFA version >= 2.0
+#[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edge<T: SampleType, S: Opt> {
+    pub id: String,
+    pub from: T,
+    pub from_dir: bool,
+    pub to: T,
+    pub to_dir: bool,
+    pub from_begin: Position,
+    pub from_end: Position,
+    pub to_begin: Position,
+    pub to_end: Position,
+    pub alignment: SeqIndex,
+    pub opt: S,
+}
+
+/// GFA2 Fragment
+///
+/// Only parsed in GFA version >= 2.0
+#[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fragment<T: SampleType, S: Opt> {
+    pub segment: T,
+    pub external: String,
+    pub external_dir: bool,
+    pub segment_begin: Position,
+    pub segment_end: Position,
+    pub fragment_begin: Position,
+    pub fragment_end: Position,
+    pub alignment: SeqIndex,
+    pub opt: S,
+}
+
+/// GFA2 Gap
+///
+/// Only parsed in GFA version >= 2.0
+#[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gap<T: SampleType, S: Opt> {
+    pub id: String,
     pub from: T,
     pub from_dir: bool,
     pub to: T,
     pub to_dir: bool,
     pub distance: i64,
+    /// None if the variance is "*"
+    pub variance: Option<i64>,
     pub opt: S,
 }
 
-/// Gfa struct
+/// Record types which reference segments
+#[derive(Debug, Clone, Copy, Ord, Eq, PartialOrd, PartialEq)]
+pub enum RecordType {
+    Link,
+    Path,
+    Walk,
+    Containment,
+    Jump,
+    Edge,
+    Fragment,
+    Gap,
+}
+
+/// Reference to a segment which does not exist
 #[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+pub struct ValidationError<T> {
+    /// Type of the record holding the reference
+    pub record: RecordType,
+    /// Index of the record in its vector (e.g. gfa.links[index])
+    pub index: usize,
+    /// The missing segment id
+    pub id: T,
+}
+
+/// The graph contains a cycle (see Gfa::topological_sort)
+#[derive(Debug, Clone, Ord, Eq, PartialOrd, PartialEq)]
+pub struct CycleError<T> {
+    /// Oriented nodes (id, forward) of one cycle, the last node links back to the first one
+    pub cycle: Vec<(T, bool)>,
+}
+
+impl<T> fmt::Display for CycleError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Graph contains a cycle of {} nodes", self.cycle.len())
+    }
+}
+
+impl<T: fmt::Debug> std::error::Error for CycleError<T> {}
+
+/// Errors while reading a GFA file
+#[derive(Debug)]
+pub enum GfaError {
+    Io(std::io::Error),
+    /// Line with a record type which is not supported (line number, record type)
+    UnknownLineType {
+        line: usize,
+        record: char,
+    },
+    /// Path step without orientation ('+' or '-')
+    BadDirection(String),
+    /// Segment ids which are in both graphs (Gfa::merge)
+    DuplicateSegments(Vec<String>),
+    /// Invalid or incompatible binary snapshot (Gfa::load_binary)
+    BadSnapshot(String),
+    /// Overlap (CIGAR) which can not be parsed or applied
+    BadOverlap(String),
+    /// Line with a missing field, a bad number or an invalid id
+    MalformedLine(String),
+    /// Range outside of a segment, or a segment which is not in the graph
+    BadRange(String),
+    /// Graphs with InternedId can not be merged, their ids are independent
+    InternedMerge,
+    /// Segment ids are not numeric, but the function needs them (Gfa::sort_segments_by)
+    NonNumericIds,
+}
+
+impl fmt::Display for GfaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GfaError::Io(e) => write!(f, "IO error: {}", e),
+            GfaError::UnknownLineType { line, record } => {
+                write!(f, "Unknown record type '{}' in line {}", record, line)
+            }
+            GfaError::BadDirection(step) => {
+                write!(f, "Path step '{}' has no orientation (+/-)", step)
+            }
+            GfaError::DuplicateSegments(ids) => {
+                write!(f, "Duplicate segment ids: {}", ids.join(", "))
+            }
+            GfaError::BadSnapshot(reason) => write!(f, "Bad binary snapshot: {}", reason),
+            GfaError::BadOverlap(reason) => write!(f, "Bad overlap: {}", reason),
+            GfaError::MalformedLine(reason) => write!(f, "Malformed line: {}", reason),
+            GfaError::BadRange(reason) => write!(f, "Bad range: {}", reason),
+            GfaError::InternedMerge => write!(f, "InternedId graphs can not be merged"),
+            GfaError::NonNumericIds => write!(f, "Segment ids are not numeric"),
+        }
+    }
+}
+
+impl std::error::Error for GfaError {}
+
+impl From<std::io::Error> for GfaError {
+    fn from(e: std::io::Error) -> Self {
+        GfaError::Io(e)
+    }
+}
+
+/// What to do with lines of an unknown record type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownLines {
+    /// Skip them silently
+    #[default]
+    Ignore,
+    /// Skip them, but store line number and record type in Gfa::unknown_lines
+    Collect,
+    /// Stop parsing and return an error
+    Error,
+}
+
+/// Options for parsing a GFA file
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub unknown_lines: UnknownLines,
+    /// Store the sequence in each segment instead of the graph sequence
+    pub store_sequences_inline: bool,
+    /// Do not store segment sequences at all (only their length)
+    ///
+    /// Sequence lookups return an empty string. Has priority over store_sequences_inline.
+    pub without_sequences: bool,
+    /// Version used if the file does not report one (otherwise DEFAULT_VERSION)
+    pub assumed_version: Option<f32>,
+    /// Split fields on tabs only (as in the spec), spaces in Z tags are kept
+    ///
+    /// By default fields are split on any whitespace.
+    pub strict_tabs: bool,
+    /// Count the records first (see Gfa::census) and preallocate the vectors
+    ///
+    /// Reads the file twice. The graph sequence is preallocated with the file size.
+    pub preallocate: bool,
+    /// Only parse these record types (e.g. ['S', 'L']), None parses all
+    ///
+    /// Other lines are skipped without being split into fields or stored.
+    /// Header lines (H) are always parsed, since the version is needed for other records.
+    pub record_types: Option<Vec<char>>,
+    /// Do not store the overlaps of links and paths (stored as missing, independent of U)
+    pub skip_overlaps: bool,
+}
+
+/// Fields of a line (see ParseOptions::strict_tabs)
+enum Fields<'a> {
+    Lenient(std::str::SplitWhitespace<'a>),
+    Strict(std::str::Split<'a, char>),
+}
+
+impl<'a> Fields<'a> {
+    /// Next field, which must exist
+    fn field(&mut self) -> Result<&'a str, GfaError> {
+        self.next()
+            .ok_or_else(|| GfaError::MalformedLine("missing field".to_string()))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        match self {
+            Fields::Lenient(fields) => fields.next(),
+            Fields::Strict(fields) => fields.next(),
+        }
+    }
+}
+
+/// Chunk sizes (in bytes) used to split a file for multithreaded parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkConfig {
+    /// Start a new chunk once the current one is larger than this
+    pub chunk_size: usize,
+    /// Lines longer than this are "long lines" (e.g. P lines)
+    pub long_line_threshold: usize,
+    /// Start a new chunk after a long line, once the current one is larger than this
+    pub long_line_chunk_size: usize,
+    /// Process the chunks in random order (may balance the threads better, but is not deterministic)
+    #[cfg(feature = "shuffle")]
+    pub shuffle: bool,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        ChunkConfig {
+            chunk_size: 40_000_000,
+            long_line_threshold: 1_000_000,
+            long_line_chunk_size: 20_000_000,
+            #[cfg(feature = "shuffle")]
+            shuffle: false,
+        }
+    }
+}
+
+/// Position of a segment in the compacted graph: id -> (unitig id, offset in bp, forward)
+pub type UnitigMap<T> = HashMap<T, (T, usize, bool)>;
+
+/// Oriented adjacency index of the links
+///
+/// Nodes are handles (2 * segment position, +1 if reverse). Every link is stored in both
+/// orientations (a+ -> b+ is also b- -> a-), entries are (link index, neighbour handle).
+/// Jumps are a separate edge class (jump index, neighbour handle), only filled by build_adjacency_with_jumps.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Adjacency {
+    outgoing: Vec<Vec<(usize, usize)>>,
+    incoming: Vec<Vec<(usize, usize)>>,
+    jumps: Vec<Vec<(usize, usize)>>,
+}
+
+impl Adjacency {
+    fn add(&mut self, link: usize, from: usize, to: usize) {
+        self.outgoing[from].push((link, to));
+        self.incoming[to].push((link, from));
+    }
+}
+
+/// Handle of a segment position in an orientation
+fn handle(position: usize, forward: bool) -> usize {
+    2 * position + !forward as usize
+}
+
+/// Gfa struct
+///
+/// Equality and order only use the records, the header and the graph sequence. Indices and caches
+/// (e.g. build_path_index) and the insertion order of the segments are not compared.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gfa<
     T: SampleType + Ord + std::marker::Send,
     S: Opt + Ord + std::marker::Send,
@@ -261,16 +879,30 @@ pub struct Gfa<
     pub jump: Vec<Jump<T, S>>,
     pub containment: Vec<Containment<T, S>>,
     pub walk: Vec<Walk<T, S>>,
+    pub edges: Vec<Edge<T, S>>,
+    pub fragments: Vec<Fragment<T, S>>,
+    pub gaps: Vec<Gap<T, S>>,
+    /// Lines with unknown record type (line number, record type)
+    ///
+    /// Only filled with UnknownLines::Collect
+    pub unknown_lines: Vec<(usize, char)>,
 
     pub is_digit: bool,
     index_of_index: Vec<usize>,
     index_low: usize,
     sequence: String,
+    path_index: Vec<Vec<usize>>,
+    path_name_index: HashMap<String, usize>,
+    interner: Interner,
+    links_sorted: bool,
+    adjacency: Adjacency,
+    /// Insertion number of each segment (same order as segments)
+    segment_order: Vec<usize>,
 }
 
 impl<
-        T: SampleType + Ord + Clone + std::marker::Send,
-        S: Opt + Ord + Clone + std::marker::Send,
+        T: SampleType + Ord + Clone + std::marker::Send + std::marker::Sync,
+        S: Opt + Ord + Clone + std::marker::Send + std::marker::Sync,
         U: Opt + std::marker::Send,
     > Default for Gfa<T, S, U>
 {
@@ -279,189 +911,516 @@ impl<
     }
 }
 
-extern crate rayon;
-
-use rayon::prelude::*;
+/// Fields of a Gfa which are compared (see Gfa)
+type GfaContent<'a, T, S, U> = (
+    (
+        &'a Header,
+        &'a [Segment<T, S>],
+        &'a [Link<T, S, U>],
+        &'a [Path<T, S, U>],
+        &'a [Jump<T, S>],
+        &'a [Containment<T, S>],
+    ),
+    (
+        &'a [Walk<T, S>],
+        &'a [Edge<T, S>],
+        &'a [Fragment<T, S>],
+        &'a [Gap<T, S>],
+        &'a [(usize, char)],
+        bool,
+        &'a str,
+        &'a [String],
+    ),
+);
 
 impl<
-        T: SampleType + Ord + Clone + std::marker::Send,
-        S: Opt + Ord + Clone + std::marker::Send,
+        T: SampleType + Ord + std::marker::Send,
+        S: Opt + Ord + std::marker::Send,
         U: Opt + std::marker::Send,
     > Gfa<T, S, U>
 {
-    pub fn new() -> Self {
-        Self {
-            header: Header::default(),
-            segments: Vec::new(),
-            sequence: String::new(),
-            links: Vec::new(),
-            paths: Vec::new(),
-            jump: Vec::new(),
-            containment: Vec::new(),
-            walk: Vec::new(),
-            is_digit: false,
+    /// Records, header, graph sequence and interned names (no indices or caches)
+    fn content(&self) -> GfaContent<'_, T, S, U> {
+        (
+            (
+                &self.header,
+                &self.segments,
+                &self.links,
+                &self.paths,
+                &self.jump,
+                &self.containment,
+            ),
+            (
+                &self.walk,
+                &self.edges,
+                &self.fragments,
+                &self.gaps,
+                &self.unknown_lines,
+                self.is_digit,
+                &self.sequence,
+                &self.interner.names,
+            ),
+        )
+    }
+}
 
-            index_of_index: Vec::new(),
-            index_low: 0,
-        }
+impl<
+        T: SampleType + Ord + std::marker::Send,
+        S: Opt + Ord + std::marker::Send,
+        U: Opt + PartialEq + std::marker::Send,
+    > PartialEq for Gfa<T, S, U>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.content() == other.content()
     }
+}
 
-    /// Parse a gfa file with multiple threads
-    ///
-    /// The function will split the file into chunks and parse them in parallel
-    /// Pre-index in chunks of 40 mb, multiple such chunks will be processed by one thread (number dependent on the number of threads)
-    pub fn parse_gfa_file_multi(file_name: &str, threads: usize) -> Gfa<T, S, U> {
-        let index = index_file(file_name);
-        let version = get_version(file_name);
+impl<
+        T: SampleType + Ord + std::marker::Send,
+        S: Opt + Ord + std::marker::Send,
+        U: Opt + Eq + std::marker::Send,
+    > Eq for Gfa<T, S, U>
+{
+}
+
+impl<
+        T: SampleType + Ord + std::marker::Send,
+        S: Opt + Ord + std::marker::Send,
+        U: Opt + Ord + std::marker::Send,
+    > PartialOrd for Gfa<T, S, U>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<
+        T: SampleType + Ord + std::marker::Send,
+        S: Opt + Ord + std::marker::Send,
+        U: Opt + Ord + std::marker::Send,
+    > Ord for Gfa<T, S, U>
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.content().cmp(&other.content())
+    }
+}
+
+impl<
+        T: SampleType + Ord + Clone + std::marker::Send + std::marker::Sync,
+        S: Opt + Ord + Clone + std::marker::Send + std::marker::Sync,
+        U: Opt + std::marker::Send,
+    > fmt::Display for Gfa<T, S, U>
+{
+    /// Short summary: number of records per type, total sequence length and id flags
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = if self.header.version_number.is_empty() {
+            "unknown"
+        } else {
+            self.header.version_number.as_str()
+        };
+        writeln!(f, "GFA graph (version {})", version)?;
+        writeln!(
+            f,
+            "Segments: {} ({} bp)",
+            self.segments.len(),
+            self.total_sequence_length()
+        )?;
+        writeln!(f, "Links: {}", self.links.len())?;
+        writeln!(f, "Paths: {}", self.paths.len())?;
+        writeln!(f, "Walks: {}", self.walk.len())?;
+        writeln!(f, "Jumps: {}", self.jump.len())?;
+        writeln!(f, "Containments: {}", self.containment.len())?;
+        writeln!(f, "Edges: {}", self.edges.len())?;
+        writeln!(f, "Fragments: {}", self.fragments.len())?;
+        writeln!(f, "Gaps: {}", self.gaps.len())?;
+        write!(
+            f,
+            "Numeric ids: {}, compact: {}",
+            self.is_digit,
+            self.is_compact()
+        )
+    }
+}
+
+extern crate rayon;
+
+use rayon::prelude::*;
+
+impl<
+        T: SampleType + Ord + Clone + std::marker::Send + std::marker::Sync,
+        S: Opt + Ord + Clone + std::marker::Send + std::marker::Sync,
+        U: Opt + std::marker::Send,
+    > Gfa<T, S, U>
+{
+    pub fn new() -> Self {
+        Self {
+            header: Header::default(),
+            segments: Vec::new(),
+            sequence: String::new(),
+            links: Vec::new(),
+            paths: Vec::new(),
+            jump: Vec::new(),
+            containment: Vec::new(),
+            walk: Vec::new(),
+            edges: Vec::new(),
+            fragments: Vec::new(),
+            gaps: Vec::new(),
+            unknown_lines: Vec::new(),
+            is_digit: false,
+
+            index_of_index: Vec::new(),
+            index_low: 0,
+            path_index: Vec::new(),
+            path_name_index: HashMap::new(),
+            interner: Interner::default(),
+            links_sorted: false,
+            adjacency: Adjacency::default(),
+            segment_order: Vec::new(),
+        }
+    }
+
+    /// Empty graph with preallocated vectors (and graph sequence in bytes)
+    pub fn with_capacity(segments: usize, links: usize, paths: usize, seq_bytes: usize) -> Self {
+        let mut graph = Self::new();
+        graph.segments.reserve(segments);
+        graph.segment_order.reserve(segments);
+        graph.links.reserve(links);
+        graph.paths.reserve(paths);
+        graph.sequence.reserve(seq_bytes);
+        graph
+    }
+
+    /// Parse a gfa file with multiple threads
+    ///
+    /// The function will split the file into chunks and parse them in parallel
+    /// Pre-index in chunks of 40 mb, multiple such chunks will be processed by one thread (number dependent on the number of threads)
+    pub fn parse_gfa_file_multi(file_name: &str, threads: usize) -> Gfa<T, S, U> {
+        Self::parse_gfa_file_multi_with(file_name, threads, &ChunkConfig::default())
+    }
+
+    /// Parse a gfa file with multiple threads, using all threads of the rayon pool
+    pub fn parse_gfa_file_multi_auto(file_name: &str) -> Gfa<T, S, U> {
+        Self::parse_gfa_file_multi(file_name, rayon::current_num_threads())
+    }
 
+    /// Parse a gfa file with multiple threads, using custom chunk sizes
+    ///
+    /// threads = 0 is treated as 1. Panics on errors, see try_parse_gfa_file_multi_with.
+    pub fn parse_gfa_file_multi_with(
+        file_name: &str,
+        threads: usize,
+        config: &ChunkConfig,
+    ) -> Gfa<T, S, U> {
+        Self::try_parse_gfa_file_multi_with(file_name, threads, config)
+            .unwrap_or_else(|e| panic!("ERROR: {}", e))
+    }
+
+    /// Parse a gfa file with multiple threads, using custom chunk sizes
+    ///
+    /// Same as parse_gfa_file_multi_with, but returns malformed lines and io errors.
+    /// InternedId can not be parsed in multiple chunks (InternedMerge), since the ids of each chunk
+    /// are independent.
+    pub fn try_parse_gfa_file_multi_with(
+        file_name: &str,
+        threads: usize,
+        config: &ChunkConfig,
+    ) -> Result<Gfa<T, S, U>, GfaError> {
+        let index = index_file_with(file_name, config);
+        let version = get_version(file_name).unwrap_or(DEFAULT_VERSION);
+
+        #[allow(unused_mut)]
         let mut byte_index = pair_with_next(&index);
-        byte_index.shuffle(&mut rand::thread_rng());
+        if byte_index.len() <= 1 {
+            // Empty file or a single chunk, nothing to split
+            return Self::parse_gfa_file_with(file_name, &ParseOptions::default());
+        }
+        #[cfg(feature = "shuffle")]
+        if config.shuffle {
+            byte_index.shuffle(&mut rand::thread_rng());
+        }
 
-        let size_chunk = (byte_index.len() + threads - 1) / threads;
+        let size_chunk = byte_index.len().div_ceil(threads.max(1));
+        debug!(
+            "Parsing {} in {} chunks ({} per thread)",
+            file_name,
+            byte_index.len(),
+            size_chunk
+        );
+        let options = ParseOptions::default();
         let result: Vec<Gfa<T, S, U>> = byte_index
             .par_chunks(size_chunk)
             .map(|x| {
                 let mut z1: Gfa<T, S, U> = Gfa::new();
+                // One file handle per worker, seeking discards the buffered data
+                let file = File::open(file_name)?;
+                let mut reader = BufReader::new(file);
+                let mut buffer = Vec::new();
                 for a in x.iter() {
-                    let file = File::open(file_name).unwrap();
-                    let mut reader = BufReader::new(file);
-                    reader.seek(SeekFrom::Start(a.0 as u64)).unwrap();
+                    trace!("Parsing bytes {} to {}", a.0, a.1);
+                    reader.seek(SeekFrom::Start(a.0 as u64))?;
                     let mut pos = a.0;
-                    for line in reader.lines() {
-                        let l = line.unwrap();
-                        pos = pos + l.len() + 1;
-                        if pos > a.1 {
+                    while pos < a.1 {
+                        buffer.clear();
+                        let read = reader.read_until(b'\n', &mut buffer)?;
+                        if read == 0 {
                             break;
                         }
-                        Gfa::read_lines(l, version, &mut z1);
+                        pos += read;
+                        let l = std::str::from_utf8(trim_newline(&buffer))
+                            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+                        Gfa::read_line(l, version, &mut z1, &options)?;
                     }
                 }
-                z1
+                Ok(z1)
             })
-            .collect();
+            .collect::<Result<_, GfaError>>()?;
 
-        // start time variable
         let mut resulting_graph: Gfa<T, S, U> = Gfa::new();
 
-        let mut offset = 0;
-        for mut graph in result {
-            graph.segments.iter_mut().for_each(|x| {
-                x.opt.adjust(offset);
-                x.sequence.adjust(offset)
-            });
-            graph.paths.iter_mut().for_each(|x| {
-                x.opt.adjust(offset);
-                x.overlap.adjust(offset)
-            });
-            graph
-                .links
-                .iter_mut()
-                .for_each(|x| x.overlap.adjust(offset));
-            graph.jump.iter_mut().for_each(|x| x.opt.adjust(offset));
-            graph.containment.iter_mut().for_each(|x| {
-                x.opt.adjust(offset);
-                x.overlap.adjust(offset)
-            });
-            graph.walk.iter_mut().for_each(|x| x.opt.adjust(offset));
+        // Interned ids depend on the parsing order, they can not be merged
+        if result.iter().filter(|x| !x.interner.is_empty()).count() > 1 {
+            return Err(GfaError::InternedMerge);
+        }
 
-            resulting_graph.segments.append(&mut graph.segments);
-            resulting_graph.paths.append(&mut graph.paths);
-            resulting_graph.links.append(&mut graph.links);
-            resulting_graph.jump.append(&mut graph.jump);
-            resulting_graph.containment.append(&mut graph.containment);
-            resulting_graph.walk.append(&mut graph.walk);
+        for graph in result {
+            resulting_graph.append(graph);
+        }
+        resulting_graph.build_index();
+        debug!(
+            "Parsed {} segments, {} links and {} paths",
+            resulting_graph.segments.len(),
+            resulting_graph.links.len(),
+            resulting_graph.paths.len()
+        );
+        Ok(resulting_graph)
+    }
 
-            resulting_graph.sequence += graph.sequence.as_str();
-            if !resulting_graph.header.version_number.is_empty() {
-                resulting_graph.header = graph.header.clone();
-            }
-            offset += graph.sequence.len()
+    /// Append all records of another graph (without building the index)
+    ///
+    /// All SeqIndex values of the other graph are moved behind the current graph sequence
+    fn append(&mut self, mut graph: Gfa<T, S, U>) {
+        let offset = self.sequence.len();
+        self.fill_segment_order();
+        graph.fill_segment_order();
+        let next = self.segment_order.iter().max().map_or(0, |x| x + 1);
+        self.segment_order
+            .extend(graph.segment_order.iter().map(|x| x + next));
+        if !graph.interner.is_empty() {
+            self.interner = std::mem::take(&mut graph.interner);
         }
-        resulting_graph.segments.sort_by(|a, b| a.id.cmp(&b.id));
-        resulting_graph.is_digit = T::is_digit();
-        resulting_graph.index_low = resulting_graph.segments[0].id.get_usize();
+        graph.segments.iter_mut().for_each(|x| {
+            SampleType::adjust(&mut x.id, offset);
+            x.opt.adjust(offset);
+            Opt::adjust(&mut x.sequence, offset)
+        });
+        graph.paths.iter_mut().for_each(|x| {
+            x.nodes.iter_mut().for_each(|y| y.adjust(offset));
+            x.opt.adjust(offset);
+            x.overlap.adjust(offset)
+        });
+        graph.links.iter_mut().for_each(|x| {
+            x.from.adjust(offset);
+            x.to.adjust(offset);
+            x.opt.adjust(offset);
+            x.overlap.adjust(offset)
+        });
+        graph.jump.iter_mut().for_each(|x| {
+            x.from.adjust(offset);
+            x.to.adjust(offset);
+            x.opt.adjust(offset)
+        });
+        graph.containment.iter_mut().for_each(|x| {
+            x.container.adjust(offset);
+            x.contained.adjust(offset);
+            x.opt.adjust(offset);
+            Opt::adjust(&mut x.overlap, offset)
+        });
+        graph.walk.iter_mut().for_each(|x| {
+            x.walk_id.iter_mut().for_each(|y| y.adjust(offset));
+            x.opt.adjust(offset)
+        });
+        graph.edges.iter_mut().for_each(|x| {
+            x.from.adjust(offset);
+            x.to.adjust(offset);
+            x.opt.adjust(offset);
+            Opt::adjust(&mut x.alignment, offset)
+        });
+        graph.fragments.iter_mut().for_each(|x| {
+            x.segment.adjust(offset);
+            x.opt.adjust(offset);
+            Opt::adjust(&mut x.alignment, offset)
+        });
+        graph.gaps.iter_mut().for_each(|x| {
+            x.from.adjust(offset);
+            x.to.adjust(offset);
+            x.opt.adjust(offset)
+        });
 
-        if T::is_digit() {
-            let mut index2index = vec![
-                0;
-                resulting_graph.segments[resulting_graph.segments.len() - 1]
-                    .id
-                    .get_usize()
-                    - resulting_graph.index_low
-                    + 1
-            ];
-            for (i, x) in resulting_graph.segments.iter().enumerate() {
-                index2index[x.id.get_usize() - resulting_graph.index_low] = i;
-            }
-            resulting_graph.index_of_index = index2index;
+        self.segments.append(&mut graph.segments);
+        self.paths.append(&mut graph.paths);
+        self.links_sorted &= graph.links.is_empty();
+        self.links.append(&mut graph.links);
+        self.jump.append(&mut graph.jump);
+        self.containment.append(&mut graph.containment);
+        self.walk.append(&mut graph.walk);
+        self.edges.append(&mut graph.edges);
+        self.fragments.append(&mut graph.fragments);
+        self.gaps.append(&mut graph.gaps);
+
+        self.unknown_lines.append(&mut graph.unknown_lines);
+
+        self.sequence += graph.sequence.as_str();
+        if self.header.version_number.is_empty() && !graph.header.version_number.is_empty() {
+            self.header = graph.header;
+        }
+    }
+
+    /// Merge another graph into this one
+    ///
+    /// Segment ids must be unique, otherwise the duplicated ids are returned (and nothing is merged).
+    /// Graphs with InternedId can not be merged (InternedMerge), their ids are independent.
+    pub fn merge(&mut self, other: Gfa<T, S, U>) -> Result<(), GfaError> {
+        if !self.interner.is_empty() && !other.interner.is_empty() {
+            return Err(GfaError::InternedMerge);
+        }
+        // Compare by number or by name, since SeqIndex ids differ for every occurrence
+        let duplicates: Vec<String> = if T::is_digit() {
+            let ids: HashSet<usize> = self.segments.iter().map(|x| x.id.get_usize()).collect();
+            other
+                .segments
+                .iter()
+                .filter(|x| ids.contains(&x.id.get_usize()))
+                .map(|x| other.original_id(&x.id))
+                .collect()
+        } else {
+            let ids: HashSet<String> = self
+                .segments
+                .iter()
+                .map(|x| self.original_id(&x.id))
+                .collect();
+            other
+                .segments
+                .iter()
+                .map(|x| other.original_id(&x.id))
+                .filter(|x| ids.contains(x))
+                .collect()
+        };
+        if !duplicates.is_empty() {
+            return Err(GfaError::DuplicateSegments(duplicates));
         }
-        resulting_graph
+
+        self.append(other);
+        self.build_index();
+        if !self.path_name_index.is_empty() {
+            self.build_path_name_index();
+        }
+        Ok(())
     }
 
     #[inline]
     /// Read lines from a GFA file
+    ///
+    /// Panics if the line is malformed
     pub fn read_lines(s: String, version_number: f32, z: &mut Gfa<T, S, U>) {
-        let mut split_line = s.split_whitespace();
-        match split_line.next().unwrap() {
+        if let Err(e) = Self::read_line(&s, version_number, z, &ParseOptions::default()) {
+            panic!("ERROR: {}", e);
+        }
+    }
+
+    #[inline]
+    /// Parse a single line into the graph
+    ///
+    /// Returns false if the record type is unknown (the line is skipped)
+    fn read_line(
+        s: &str,
+        version_number: f32,
+        z: &mut Gfa<T, S, U>,
+        options: &ParseOptions,
+    ) -> Result<bool, GfaError> {
+        if s.is_empty() {
+            return Ok(true);
+        }
+        let mut split_line = if options.strict_tabs {
+            Fields::Strict(s.split('\t'))
+        } else {
+            Fields::Lenient(s.split_whitespace())
+        };
+        let record = match split_line.next() {
+            Some(record) => record,
+            None => return Ok(true),
+        };
+        if let Some(types) = &options.record_types {
+            let mut chars = record.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c == 'H' || types.contains(&c) => {}
+                _ => return Ok(true),
+            }
+        }
+        match record {
             "S" => {
-                let name = split_line.next().unwrap();
-                if version_number <= 2.0 {
-                    let sequence = split_line.next().unwrap();
-                    let size = sequence.len() as u32;
-                    let opt = split_line.next();
-                    z.segments.push(Segment {
-                        id: T::parse1(name, &mut z.sequence),
-                        sequence: SeqIndex::parse1(sequence, &mut z.sequence),
-                        length: size,
-                        opt: S::parse1(opt, &mut z.sequence),
-                    });
+                let name = split_line.field()?;
+                let (mut size, sequence) = if version_number < 2.0 {
+                    let sequence = split_line.field()?;
+                    (sequence.len() as u32, sequence)
                 } else {
-                    let sequence = split_line.next().unwrap();
-                    let size = split_line.next().unwrap().parse().unwrap();
-                    let opt = split_line.next();
-
-                    z.segments.push(Segment {
-                        id: T::parse1(name, &mut z.sequence),
-                        sequence: SeqIndex::parse1(sequence, &mut z.sequence),
-                        length: size,
-                        opt: S::parse1(opt, &mut z.sequence),
-                    });
+                    // GFA2: S <sid> <slen> <sequence>
+                    let size = parse_number(split_line.field()?)?;
+                    (size, split_line.field()?)
+                };
+                // All optional fields, as they are in the line
+                let opt = split_line.next().map(|first| rest_of_line(s, first));
+
+                // "*" is no sequence, GFA1 has the length in the (optional) LN tag
+                let sequence = if sequence == "*" { "" } else { sequence };
+                if version_number < 2.0 && sequence.is_empty() {
+                    size = opt
+                        .into_iter()
+                        .chain(split_line)
+                        .find_map(|x| x.strip_prefix("LN:i:"))
+                        .and_then(|x| x.parse().ok())
+                        .unwrap_or(0);
                 }
+
+                let id = parse_id(name, &mut z.sequence, &mut z.interner)?;
+                let (sequence, inline_sequence) = if options.without_sequences {
+                    (SeqIndex::parse1("", &mut z.sequence), None)
+                } else if options.store_sequences_inline {
+                    (SeqIndex::parse1("", &mut z.sequence), Some(sequence.into()))
+                } else {
+                    (SeqIndex::parse1(sequence, &mut z.sequence), None)
+                };
+                z.segments.push(Segment {
+                    id,
+                    sequence,
+                    length: size,
+                    opt: S::parse1(opt, &mut z.sequence),
+                    inline_sequence,
+                });
             }
-            "H" => {
-                let header = Header::from_string(&s);
-                z.header = header;
-            }
+            "H" => z.header.add_fields(split_line),
             "L" => {
-                let from = split_line.next().unwrap();
-                let from_dir = split_line.next().unwrap() == "+";
-                let to = split_line.next().unwrap();
-                let to_dir = split_line.next().unwrap() == "+";
-                let overlap = split_line.next();
+                let from = split_line.field()?;
+                let from_dir = parse_dir(split_line.field()?)?;
+                let to = split_line.field()?;
+                let to_dir = parse_dir(split_line.field()?)?;
+                let overlap = split_line.next().filter(|_| !options.skip_overlaps);
                 let opt = split_line.next();
                 z.links.push(Link {
-                    from: T::parse1(from, &mut z.sequence),
+                    from: parse_id(from, &mut z.sequence, &mut z.interner)?,
                     from_dir,
-                    to: T::parse1(to, &mut z.sequence),
+                    to: parse_id(to, &mut z.sequence, &mut z.interner)?,
                     to_dir,
                     overlap: U::parse1(overlap, &mut z.sequence),
                     opt: S::parse1(opt, &mut z.sequence),
                 });
             }
             "P" => {
-                let name = split_line.next().unwrap().to_owned();
-                //let (dirs, node_id) = path_parser(split_line.next().unwrap(), &mut z.sequence);
-                let a = split_line.next().unwrap().split(',');
-                let (mut dirs, mut node_id) = (
-                    Vec::with_capacity(a.clone().count()),
-                    Vec::with_capacity(a.clone().count()),
-                );
-                for d in a {
-                    dirs.push(&d[d.len() - 1..] == "+");
-                    node_id.push(SampleType::parse1(&d[..d.len() - 1], &mut z.sequence));
-                }
+                let name = split_line.field()?.to_owned();
+                let (dirs, node_id) =
+                    path_parser(split_line.field()?, &mut z.sequence, &mut z.interner)?;
 
-                let k = U::parse1(split_line.next(), &mut z.sequence);
+                let overlap = split_line.next().filter(|_| !options.skip_overlaps);
+                let k = U::parse1(overlap, &mut z.sequence);
                 let k2 = S::parse1(split_line.next(), &mut z.sequence);
                 z.paths.push(Path {
                     name,
@@ -472,12 +1431,12 @@ impl<
                 });
             }
             "W" => {
-                let sample_id = split_line.next().unwrap().to_owned();
-                let hap_index = split_line.next().unwrap().parse().unwrap();
-                let seq_id = split_line.next().unwrap().to_owned();
-                let seq_start = split_line.next().unwrap().parse().unwrap();
-                let seq_end = split_line.next().unwrap().parse().unwrap();
-                let (w1, w2) = walk_parser(split_line.next().unwrap(), &mut z.sequence);
+                let sample_id = split_line.field()?.to_owned();
+                let hap_index = parse_number(split_line.field()?)?;
+                let seq_id = split_line.field()?.to_owned();
+                let seq_start = parse_number(split_line.field()?)?;
+                let seq_end = parse_number(split_line.field()?)?;
+                let (w1, w2) = walk_parser(split_line.field()?, &mut z.sequence, &mut z.interner)?;
                 let opt = S::parse1(split_line.next(), &mut z.sequence);
                 z.walk.push(Walk {
                     sample_id,
@@ -491,163 +1450,2535 @@ impl<
                 });
             }
             "C" => {
-                let container = split_line.next().unwrap();
-                let container_dir = split_line.next().unwrap() == "+";
-                let contained = split_line.next().unwrap();
-                let contained_dir = split_line.next().unwrap() == "+";
-                let pos = split_line.next().unwrap().parse().unwrap();
-                let overlap = split_line.next().unwrap();
+                let container = split_line.field()?;
+                let container_dir = parse_dir(split_line.field()?)?;
+                let contained = split_line.field()?;
+                let contained_dir = parse_dir(split_line.field()?)?;
+                let pos = parse_number(split_line.field()?)?;
+                let overlap = split_line.field()?;
                 let opt = split_line.next();
                 z.containment.push(Containment {
-                    container: T::parse1(container, &mut z.sequence),
+                    container: parse_id(container, &mut z.sequence, &mut z.interner)?,
                     container_dir,
-                    contained: T::parse1(contained, &mut z.sequence),
+                    contained: parse_id(contained, &mut z.sequence, &mut z.interner)?,
                     contained_dir,
                     pos,
                     overlap: SeqIndex::parse1(overlap, &mut z.sequence),
                     opt: S::parse1(opt, &mut z.sequence),
                 });
             }
+            "E" if version_number >= 2.0 => {
+                let id = split_line.field()?.to_owned();
+                let (from, from_dir) = parse_reference(split_line.field()?)?;
+                let (to, to_dir) = parse_reference(split_line.field()?)?;
+                let from_begin = Position::parse(split_line.field()?)?;
+                let from_end = Position::parse(split_line.field()?)?;
+                let to_begin = Position::parse(split_line.field()?)?;
+                let to_end = Position::parse(split_line.field()?)?;
+                let alignment = split_line.field()?;
+                let opt = split_line.next();
+                z.edges.push(Edge {
+                    id,
+                    from: parse_id(from, &mut z.sequence, &mut z.interner)?,
+                    from_dir,
+                    to: parse_id(to, &mut z.sequence, &mut z.interner)?,
+                    to_dir,
+                    from_begin,
+                    from_end,
+                    to_begin,
+                    to_end,
+                    alignment: SeqIndex::parse1(alignment, &mut z.sequence),
+                    opt: S::parse1(opt, &mut z.sequence),
+                });
+            }
+            "F" if version_number >= 2.0 => {
+                let segment = split_line.field()?;
+                let (external, external_dir) = parse_reference(split_line.field()?)?;
+                let segment_begin = Position::parse(split_line.field()?)?;
+                let segment_end = Position::parse(split_line.field()?)?;
+                let fragment_begin = Position::parse(split_line.field()?)?;
+                let fragment_end = Position::parse(split_line.field()?)?;
+                let alignment = split_line.field()?;
+                let opt = split_line.next();
+                z.fragments.push(Fragment {
+                    segment: parse_id(segment, &mut z.sequence, &mut z.interner)?,
+                    external: external.to_owned(),
+                    external_dir,
+                    segment_begin,
+                    segment_end,
+                    fragment_begin,
+                    fragment_end,
+                    alignment: SeqIndex::parse1(alignment, &mut z.sequence),
+                    opt: S::parse1(opt, &mut z.sequence),
+                });
+            }
+            "G" if version_number >= 2.0 => {
+                let id = split_line.field()?.to_owned();
+                let (from, from_dir) = parse_reference(split_line.field()?)?;
+                let (to, to_dir) = parse_reference(split_line.field()?)?;
+                let distance = parse_number(split_line.field()?)?;
+                let variance = match split_line.field()? {
+                    "*" => None,
+                    x => Some(parse_number(x)?),
+                };
+                let opt = split_line.next();
+                z.gaps.push(Gap {
+                    id,
+                    from: parse_id(from, &mut z.sequence, &mut z.interner)?,
+                    from_dir,
+                    to: parse_id(to, &mut z.sequence, &mut z.interner)?,
+                    to_dir,
+                    distance,
+                    variance,
+                    opt: S::parse1(opt, &mut z.sequence),
+                });
+            }
             "J" => {
-                let from = split_line.next().unwrap();
-                let from_dir = split_line.next().unwrap() == "+";
-                let to = split_line.next().unwrap();
-                let to_dir = split_line.next().unwrap() == "+";
-                let distance = parse_dumb(split_line.next().unwrap());
+                let from = split_line.field()?;
+                let from_dir = parse_dir(split_line.field()?)?;
+                let to = split_line.field()?;
+                let to_dir = parse_dir(split_line.field()?)?;
+                let distance = parse_dumb(split_line.field()?)?;
                 let opt = split_line.next();
                 z.jump.push(Jump {
-                    from: T::parse1(from, &mut z.sequence),
+                    from: parse_id(from, &mut z.sequence, &mut z.interner)?,
                     from_dir,
-                    to: T::parse1(to, &mut z.sequence),
+                    to: parse_id(to, &mut z.sequence, &mut z.interner)?,
                     to_dir,
                     distance,
                     opt: S::parse1(opt, &mut z.sequence),
                 });
             }
-            _ => {}
+            _ => return Ok(record.starts_with('#')),
         }
+        Ok(true)
     }
 
     /// Parse a GFA file
+    ///
+    /// Returns an empty graph if the file does not exist
     pub fn parse_gfa_file(file_name: &str) -> Gfa<T, S, U> {
         if file_path::new(file_name).exists() {
-            let file = File::open(file_name).expect("ERROR: CAN NOT READ FILE\n");
-            let reader = BufReader::new(file);
-
-            let version_number = get_version(file_name);
-            let mut resulting_graph: Gfa<T, S, U> = Gfa::new();
+            Self::parse_gfa_file_with(file_name, &ParseOptions::default())
+                .expect("ERROR: CAN NOT READ FILE\n")
+        } else {
+            Gfa::new()
+        }
+    }
 
-            // Iterate over lines
-            for line in reader.lines() {
-                Self::read_lines(line.unwrap(), version_number, &mut resulting_graph);
+    /// Number of lines per record type (first character of the line), without building a graph
+    ///
+    /// Empty lines are not counted.
+    pub fn census(file_name: &str) -> Result<HashMap<char, u64>, GfaError> {
+        let mut reader = BufReader::new(File::open(file_name)?);
+        let mut counts = HashMap::new();
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            if reader.read_until(b'\n', &mut buffer)? == 0 {
+                break;
             }
-            resulting_graph.segments.sort_by(|a, b| a.id.cmp(&b.id));
-            resulting_graph.is_digit = T::is_digit();
-            resulting_graph.index_low = resulting_graph.segments[0].id.get_usize();
+            match trim_newline(&buffer).first() {
+                Some(&record) => *counts.entry(record as char).or_insert(0) += 1,
+                None => continue,
+            }
+        }
+        Ok(counts)
+    }
 
-            if T::is_digit() {
-                let mut aa = vec![
-                    0;
-                    resulting_graph.segments[resulting_graph.segments.len() - 1]
-                        .id
-                        .get_usize()
-                        - resulting_graph.index_low
-                        + 1
-                ];
-                for (i, x) in resulting_graph.segments.iter().enumerate() {
-                    aa[x.id.get_usize() - resulting_graph.index_low] = i;
-                }
-                resulting_graph.index_of_index = aa;
-            }
-            resulting_graph
+    /// Parse multiple GFA files into one graph (see merge)
+    ///
+    /// Segment ids must be unique across all files, otherwise the duplicated ids are returned.
+    /// Multiple files with InternedId can not be merged (InternedMerge).
+    pub fn parse_gfa_files(file_names: &[&str]) -> Result<Gfa<T, S, U>, GfaError> {
+        let mut graph = Gfa::new();
+        for file_name in file_names.iter() {
+            graph.merge(Self::parse_gfa_file_with(
+                file_name,
+                &ParseOptions::default(),
+            )?)?;
+        }
+        Ok(graph)
+    }
+
+    /// Parse only some record types of a GFA file (e.g. &['S', 'L'] for the topology)
+    ///
+    /// The header is always parsed.
+    /// Shortcut for parse_gfa_file_with and ParseOptions::record_types
+    pub fn parse_gfa_file_with_filter(
+        file_name: &str,
+        types: &[char],
+    ) -> Result<Gfa<T, S, U>, GfaError> {
+        let options = ParseOptions {
+            record_types: Some(types.to_vec()),
+            ..Default::default()
+        };
+        Self::parse_gfa_file_with(file_name, &options)
+    }
+
+    /// Parse a GFA file with additional options
+    pub fn parse_gfa_file_with(
+        file_name: &str,
+        options: &ParseOptions,
+    ) -> Result<Gfa<T, S, U>, GfaError> {
+        Self::parse_gfa_file_with_progress(file_name, options, |_| {})
+    }
+
+    /// Parse a GFA file with additional options, reporting the progress
+    ///
+    /// progress is called with the number of bytes read so far, about every MB and once at the end
+    pub fn parse_gfa_file_with_progress(
+        file_name: &str,
+        options: &ParseOptions,
+        progress: impl FnMut(u64),
+    ) -> Result<Gfa<T, S, U>, GfaError> {
+        Self::parse_file(file_name, options, progress, None)
+    }
+
+    /// Parse a GFA file, skipping lines which can not be parsed
+    ///
+    /// Returns the graph of all valid lines and the errors with their line number (1-based).
+    /// If the file can not be read, the graph is empty and the error has line number 0.
+    pub fn parse_lenient(file_name: &str) -> (Gfa<T, S, U>, Vec<(usize, GfaError)>) {
+        let mut errors = Vec::new();
+        match Self::parse_file(
+            file_name,
+            &ParseOptions::default(),
+            |_| {},
+            Some(&mut errors),
+        ) {
+            Ok(graph) => (graph, errors),
+            Err(e) => (Gfa::new(), vec![(0, e)]),
+        }
+    }
+
+    /// Parse a GFA file line by line
+    ///
+    /// Errors of single lines are collected in errors if given, otherwise parsing stops
+    fn parse_file(
+        file_name: &str,
+        options: &ParseOptions,
+        mut progress: impl FnMut(u64),
+        mut errors: Option<&mut Vec<(usize, GfaError)>>,
+    ) -> Result<Gfa<T, S, U>, GfaError> {
+        let file = File::open(file_name)?;
+        let mut reader = BufReader::new(file);
+
+        let version_number = get_version(file_name)
+            .or(options.assumed_version)
+            .unwrap_or(DEFAULT_VERSION);
+        debug!("Parsing {} (GFA version {})", file_name, version_number);
+        let mut resulting_graph: Gfa<T, S, U> = if options.preallocate {
+            let counts = Self::census(file_name)?;
+            let count = |record: char| counts.get(&record).copied().unwrap_or(0) as usize;
+            // The graph sequence only holds parts of the file
+            let seq_bytes = if options.without_sequences || options.store_sequences_inline {
+                0
+            } else {
+                std::fs::metadata(file_name)?.len() as usize
+            };
+            Gfa::with_capacity(count('S'), count('L'), count('P'), seq_bytes)
         } else {
             Gfa::new()
+        };
+
+        // Iterate over lines
+        let mut buffer = String::new();
+        let mut bytes = 0;
+        let mut reported = 0;
+        for i in 0.. {
+            buffer.clear();
+            let length = reader.read_line(&mut buffer)?;
+            if length == 0 {
+                break;
+            }
+            bytes += length as u64;
+            if bytes - reported >= PROGRESS_STEP {
+                progress(bytes);
+                reported = bytes;
+            }
+            let line = buffer.strip_suffix('\n').unwrap_or(&buffer);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let result = Self::read_line(line, version_number, &mut resulting_graph, options)
+                .and_then(|known| {
+                    if known {
+                        return Ok(());
+                    }
+                    let record = line.chars().next().unwrap_or_default();
+                    match options.unknown_lines {
+                        UnknownLines::Ignore => {}
+                        UnknownLines::Collect => {
+                            resulting_graph.unknown_lines.push((i + 1, record))
+                        }
+                        UnknownLines::Error => {
+                            return Err(GfaError::UnknownLineType {
+                                line: i + 1,
+                                record,
+                            })
+                        }
+                    }
+                    Ok(())
+                });
+            if let Err(e) = result {
+                match errors.as_deref_mut() {
+                    Some(errors) => errors.push((i + 1, e)),
+                    None => return Err(e),
+                }
+            }
+        }
+        progress(bytes);
+        resulting_graph.build_index();
+        debug!(
+            "Parsed {} segments, {} links and {} paths",
+            resulting_graph.segments.len(),
+            resulting_graph.links.len(),
+            resulting_graph.paths.len()
+        );
+        Ok(resulting_graph)
+    }
+
+    #[cfg(feature = "mmap")]
+    /// Parse a GFA file, keeping the segment sequences in the memory-mapped file
+    ///
+    /// The file is read once to parse the graph, but sequences are not copied to the heap.
+    /// The file must not be modified while the returned graph is alive.
+    pub fn parse_gfa_file_mmap(file_name: &str) -> Result<MappedGfa<T, S, U>, GfaError> {
+        let file = File::open(file_name)?;
+        // SAFETY: the map is read-only, modifying the file while mapped is documented as not allowed
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+        let version_number = get_version(file_name).unwrap_or(DEFAULT_VERSION);
+        let options = ParseOptions {
+            without_sequences: true,
+            ..Default::default()
+        };
+        let column = if version_number < 2.0 { 2 } else { 3 };
+        let mut graph: Gfa<T, S, U> = Gfa::new();
+
+        let mut start = 0;
+        for line in mmap.split(|x| *x == b'\n') {
+            let line_start = start;
+            start += line.len() + 1;
+            let line = std::str::from_utf8(trim_newline(line))
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            let count = graph.segments.len();
+            Self::read_line(line, version_number, &mut graph, &options)?;
+
+            // Point the new segment to its sequence in the file
+            if graph.segments.len() > count {
+                let sequence = line.split_whitespace().nth(column).unwrap();
+                if sequence != "*" {
+                    let offset = line_start + (sequence.as_ptr() as usize - line.as_ptr() as usize);
+                    graph.segments[count].sequence = SeqIndex([offset, offset + sequence.len()]);
+                }
+            }
+        }
+        graph.build_index();
+        // Keep the file offsets out of the graph, its own getters only know the graph sequence
+        let offsets = graph
+            .segments
+            .iter_mut()
+            .map(|segment| std::mem::replace(&mut segment.sequence, SeqIndex([0, 0])))
+            .collect();
+        Ok(MappedGfa {
+            graph,
+            mmap,
+            offsets,
+        })
+    }
+
+    #[cfg(feature = "bincode")]
+    /// Save the graph (including all indices) as a binary snapshot (bincode)
+    ///
+    /// The format is not stable across crate versions, the file starts with a magic number and the
+    /// crate version. load_binary rejects snapshots of other versions.
+    pub fn save_binary(&self, file_name: &str) -> Result<(), GfaError>
+    where
+        T: serde::Serialize,
+        S: serde::Serialize,
+        U: serde::Serialize,
+    {
+        let mut writer = std::io::BufWriter::new(File::create(file_name)?);
+        writer.write_all(BINARY_MAGIC)?;
+        bincode::serialize_into(&mut writer, env!("CARGO_PKG_VERSION"))
+            .and_then(|_| bincode::serialize_into(&mut writer, self))
+            .map_err(|e| GfaError::BadSnapshot(e.to_string()))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "bincode")]
+    /// Load a graph saved with save_binary
+    pub fn load_binary(file_name: &str) -> Result<Gfa<T, S, U>, GfaError>
+    where
+        T: serde::de::DeserializeOwned,
+        S: serde::de::DeserializeOwned,
+        U: serde::de::DeserializeOwned,
+    {
+        let mut reader = BufReader::new(File::open(file_name)?);
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != BINARY_MAGIC {
+            return Err(GfaError::BadSnapshot(
+                "not a gfa-reader snapshot".to_string(),
+            ));
+        }
+        let version: String = bincode::deserialize_from(&mut reader)
+            .map_err(|e| GfaError::BadSnapshot(e.to_string()))?;
+        if version != env!("CARGO_PKG_VERSION") {
+            return Err(GfaError::BadSnapshot(format!(
+                "snapshot of version {}, expected {}",
+                version,
+                env!("CARGO_PKG_VERSION")
+            )));
+        }
+        bincode::deserialize_from(&mut reader).map_err(|e| GfaError::BadSnapshot(e.to_string()))
+    }
+
+    /// Number new segments in insertion order (if segments were removed directly, all are renumbered)
+    fn fill_segment_order(&mut self) {
+        if self.segment_order.len() > self.segments.len() {
+            self.segment_order.clear();
+        }
+        let next = self.segment_order.iter().max().map_or(0, |x| x + 1);
+        let missing = self.segments.len() - self.segment_order.len();
+        self.segment_order.extend(next..next + missing);
+    }
+
+    /// Positions (in segments) of all segments in file order
+    ///
+    /// Segments are sorted by id, this is the order in which they were parsed or added.
+    /// Not kept by subgraph and by parse_gfa_file_multi with shuffled chunks.
+    pub fn file_order(&self) -> Vec<usize> {
+        let mut positions: Vec<usize> = (0..self.segments.len()).collect();
+        positions.sort_by_key(|&x| self.segment_order.get(x).copied().unwrap_or(usize::MAX));
+        positions
+    }
+
+    /// Sort the segments by id and build the id -> position index
+    ///
+    /// An empty graph has no index
+    fn build_index(&mut self) {
+        // Sort the insertion numbers together with the segments (stable sort)
+        self.fill_segment_order();
+        let order = std::mem::take(&mut self.segment_order);
+        let mut segments: Vec<(Segment<T, S>, usize)> = std::mem::take(&mut self.segments)
+            .into_iter()
+            .zip(order)
+            .collect();
+        segments.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+        (self.segments, self.segment_order) = segments.into_iter().unzip();
+        self.is_digit = T::is_digit();
+        self.index_of_index = Vec::new();
+        self.index_low = 0;
+        self.path_index = Vec::new();
+        self.adjacency = Adjacency::default();
+        if self.segments.is_empty() {
+            return;
+        }
+        self.index_low = self.segments[0].id.get_usize();
+
+        if T::is_digit() {
+            let mut index = vec![0; self.get_index_high() - self.index_low + 1];
+
+            // Segments are sorted, so each chunk of segments writes to its own range of the index.
+            // Chunks never start within a run of equal ids, which keeps the ranges disjoint.
+            let len = self.segments.len();
+            let chunk_size = len.div_ceil(rayon::current_num_threads()).max(1);
+            let mut starts = vec![0];
+            let mut start = chunk_size;
+            while start < len {
+                while start < len && self.segments[start].id == self.segments[start - 1].id {
+                    start += 1;
+                }
+                if start < len {
+                    starts.push(start);
+                }
+                start += chunk_size;
+            }
+
+            let mut chunks = Vec::with_capacity(starts.len());
+            let mut rest = index.as_mut_slice();
+            let mut offset = self.index_low;
+            for (i, &start) in starts.iter().enumerate() {
+                let end = starts.get(i + 1).copied().unwrap_or(len);
+                let split = match self.segments.get(end) {
+                    Some(next) => next.id.get_usize() - offset,
+                    None => rest.len(),
+                };
+                let (current, remaining) = rest.split_at_mut(split);
+                chunks.push((current, offset, start, &self.segments[start..end]));
+                rest = remaining;
+                offset += split;
+            }
+
+            chunks
+                .into_par_iter()
+                .for_each(|(slice, offset, start, segments)| {
+                    for (i, x) in segments.iter().enumerate() {
+                        slice[x.id.get_usize() - offset] = start + i;
+                    }
+                });
+            self.index_of_index = index;
+        }
+    }
+
+    /// Get the InternedId of a name, adding the name if it is new
+    ///
+    /// Only needed when building a graph with InternedId (see add_segment)
+    pub fn intern(&mut self, name: &str) -> InternedId {
+        self.interner.intern(name)
+    }
+
+    /// Id as it appeared in the file (works for every id type)
+    pub fn original_id(&self, id: &T) -> String {
+        id.id_string(&self.sequence, &self.interner)
+    }
+
+    /// Mapping between string ids and InternedId (empty for other id types)
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    /// Add a segment
+    ///
+    /// The sequence is appended to the graph sequence, segments are sorted and indexed again.
+    /// This is O(n log n) for every call, use add_segments to add many segments at once.
+    pub fn add_segment(&mut self, id: T, sequence: &str, opt: S) {
+        self.add_segments(std::iter::once((id, sequence, opt)));
+    }
+
+    /// Add many segments (id, sequence, opt)
+    ///
+    /// Same as add_segment, but segments are sorted and indexed only once after adding all of them.
+    pub fn add_segments<'a>(&mut self, segments: impl IntoIterator<Item = (T, &'a str, S)>) {
+        for (id, sequence, opt) in segments {
+            self.segments.push(Segment {
+                id,
+                sequence: SeqIndex::parse1(sequence, &mut self.sequence),
+                length: sequence.len() as u32,
+                opt,
+                inline_sequence: None,
+            });
+        }
+        self.build_index();
+    }
+
+    /// Add a link
+    pub fn add_link(&mut self, from: T, from_dir: bool, to: T, to_dir: bool, overlap: U, opt: S) {
+        self.links_sorted = false;
+        self.adjacency = Adjacency::default();
+        self.links.push(Link {
+            from,
+            to,
+            from_dir,
+            to_dir,
+            overlap,
+            opt,
+        });
+    }
+
+    /// Add a path
+    ///
+    /// Nodes and directions must have the same length
+    pub fn add_path(&mut self, name: &str, nodes: Vec<T>, dir: Vec<bool>, overlap: U, opt: S) {
+        assert_eq!(
+            nodes.len(),
+            dir.len(),
+            "Nodes and directions differ in length"
+        );
+        if !self.path_name_index.is_empty() {
+            self.path_name_index
+                .entry(name.to_owned())
+                .or_insert(self.paths.len());
+        }
+        self.path_index = Vec::new();
+        self.paths.push(Path {
+            name: name.to_owned(),
+            dir,
+            nodes,
+            overlap,
+            opt,
+        });
+    }
+
+    /// Reset all optional fields (opt) and release unused memory
+    ///
+    /// The raw tags stay in the graph sequence, since they are interleaved with sequences and ids.
+    pub fn clear_optional(&mut self) {
+        let mut empty = String::new();
+        self.segments
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.links
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.paths
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.walk
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.containment
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.jump
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.edges
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.fragments
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.gaps
+            .iter_mut()
+            .for_each(|x| x.opt = S::parse1(None, &mut empty));
+        self.shrink_to_fit();
+    }
+
+    /// Release unused capacity of all records and the graph sequence
+    pub fn shrink_to_fit(&mut self) {
+        self.segments.shrink_to_fit();
+        self.segment_order.shrink_to_fit();
+        self.links.shrink_to_fit();
+        self.paths.shrink_to_fit();
+        self.walk.shrink_to_fit();
+        self.containment.shrink_to_fit();
+        self.jump.shrink_to_fit();
+        self.edges.shrink_to_fit();
+        self.fragments.shrink_to_fit();
+        self.gaps.shrink_to_fit();
+        self.sequence.shrink_to_fit();
+    }
+
+    /// Convert Walk to Path
+    ///
+    /// The walks are removed afterwards (see walk_to_path_keep)
+    pub fn walk_to_path(&mut self, sep: &str) {
+        self.walk_to_path_keep(sep);
+        self.walk = Vec::new();
+    }
+
+    /// Convert Walk to Path, but keep the walks
+    ///
+    /// The converted paths are appended to the existing paths, the path indices are updated.
+    pub fn walk_to_path_keep(&mut self, sep: &str) {
+        for walk in self.walk.iter() {
+            let f = walk.walk_id.to_vec();
+            let o = U::parse1(None, &mut self.sequence);
+            let n = walk.sample_id.to_owned()
+                + sep
+                + &walk.hap_index.to_owned().to_string()
+                + sep
+                + &walk.seq_id
+                + ":"
+                + &walk.seq_start.to_owned().to_string()
+                + "-"
+                + &walk.seq_end.to_owned().to_string();
+            let w = walk.walk_dir.to_vec();
+            let opt = walk.opt.clone();
+            self.paths.push(Path {
+                name: n,
+                dir: w,
+                nodes: f,
+                overlap: o,
+                opt,
+            });
+        }
+        self.path_index = Vec::new();
+        if !self.path_name_index.is_empty() {
+            self.build_path_name_index();
+        }
+    }
+
+    /// Convert Path to Walk (inverse of walk_to_path)
+    ///
+    /// Path names must have the format sample{sep}hap{sep}contig:start-end.
+    /// Paths with other names are kept as paths.
+    pub fn path_to_walk(&mut self, sep: &str) {
+        let paths = std::mem::take(&mut self.paths);
+        for path in paths {
+            match split_walk_name(&path.name, sep) {
+                Some((sample_id, hap_index, seq_id, seq_start, seq_end)) => {
+                    self.walk.push(Walk {
+                        sample_id: sample_id.to_owned(),
+                        hap_index,
+                        seq_id: seq_id.to_owned(),
+                        seq_start,
+                        seq_end,
+                        walk_dir: path.dir,
+                        walk_id: path.nodes,
+                        opt: path.opt,
+                    });
+                }
+                None => self.paths.push(path),
+            }
+        }
+        self.path_index = Vec::new();
+        if !self.path_name_index.is_empty() {
+            self.build_path_name_index();
+        }
+    }
+
+    /// Convert PanSN paths (sample{del}hap{del}contig) to walks
+    ///
+    /// The walk covers the whole contig: start is 0, end is the path length (sum of the segment lengths).
+    /// Paths with other names are kept as paths, their names are returned.
+    pub fn paths_to_walks_pansn(&mut self, del: &str) -> Vec<String> {
+        let mut rejected = Vec::new();
+        let paths = std::mem::take(&mut self.paths);
+        for path in paths {
+            let fields: Vec<&str> = path.name.split(del).collect();
+            let hap_index = fields.get(1).and_then(|x| x.parse::<u32>().ok());
+            match hap_index {
+                Some(hap_index) if fields.len() == 3 => {
+                    let length: usize = path
+                        .nodes
+                        .iter()
+                        .filter_map(|x| self.position_of(x))
+                        .map(|x| self.segments[x].length as usize)
+                        .sum();
+                    self.walk.push(Walk {
+                        sample_id: fields[0].to_owned(),
+                        hap_index,
+                        seq_id: fields[2].to_owned(),
+                        seq_start: 0,
+                        seq_end: length as i32,
+                        walk_dir: path.dir,
+                        walk_id: path.nodes,
+                        opt: path.opt,
+                    });
+                }
+                _ => {
+                    rejected.push(path.name.clone());
+                    self.paths.push(path);
+                }
+            }
+        }
+        self.path_index = Vec::new();
+        if !self.path_name_index.is_empty() {
+            self.build_path_name_index();
+        }
+        rejected
+    }
+
+    /// Check the sequences of all segments (see validate_sequence)
+    ///
+    /// Returns the id of each invalid segment and the offset of the first invalid character
+    pub fn validate_sequences(&self) -> Vec<(T, usize)> {
+        self.segments
+            .iter()
+            .filter_map(|x| {
+                validate_sequence(x.sequence_str(&self.sequence)).map(|pos| (x.id.clone(), pos))
+            })
+            .collect()
+    }
+
+    /// Total length of all segments
+    ///
+    /// Uses the segment lengths, also works for segments without (stored) sequence
+    pub fn total_sequence_length(&self) -> u64 {
+        self.segments.iter().map(|x| x.length as u64).sum()
+    }
+
+    /// Number of segments per length bucket
+    ///
+    /// The key is the lower bound of the bucket (length / bucket_size * bucket_size)
+    pub fn length_histogram(&self, bucket_size: u32) -> BTreeMap<u32, u64> {
+        assert!(bucket_size > 0, "Bucket size must be larger than 0");
+        let mut histogram = BTreeMap::new();
+        for segment in self.segments.iter() {
+            *histogram
+                .entry(segment.length / bucket_size * bucket_size)
+                .or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// GC content of all segments together (see Segment::gc_content)
+    pub fn overall_gc(&self) -> f32 {
+        let sequence = self.sequence.as_str();
+        let (gc, acgt) = self
+            .segments
+            .par_iter()
+            .map(|x| gc_counts(x.sequence_str(sequence)))
+            .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+        gc_fraction(gc, acgt)
+    }
+
+    /// Depth of each segment from a numeric tag (e.g. "DP" for DP:f:2.5 or DP:i:3)
+    ///
+    /// All optional fields of a segment are checked, they are only stored with S = SeqIndex. Segments
+    /// without the tag (or with a non-numeric value) are skipped.
+    pub fn segment_depths(&self, tag: &str) -> Vec<(T, f32)> {
+        self.segments
+            .iter()
+            .filter_map(|segment| {
+                let depth = segment
+                    .opt
+                    .as_str(&self.sequence)
+                    .split_whitespace()
+                    .find_map(|field| {
+                        let mut parts = field.splitn(3, ':');
+                        match (parts.next(), parts.next(), parts.next()) {
+                            (Some(name), Some("i" | "f"), Some(value)) if name == tag => {
+                                value.parse().ok()
+                            }
+                            _ => None,
+                        }
+                    })?;
+                Some((segment.id.clone(), depth))
+            })
+            .collect()
+    }
+
+    /// Canonical k-mer counts of all segment sequences (see count_kmers_with)
+    pub fn count_kmers(&self, k: usize) -> HashMap<Vec<u8>, u64> {
+        self.count_kmers_with(k, true)
+    }
+
+    /// K-mer counts of all segment sequences
+    ///
+    /// K-mers are upper case and do not span links. K-mers with other bases than A, C, G and T are
+    /// skipped. If canonical is set, the smaller of a k-mer and its reverse complement is counted.
+    /// Segments are counted in parallel.
+    pub fn count_kmers_with(&self, k: usize, canonical: bool) -> HashMap<Vec<u8>, u64> {
+        assert!(k > 0, "k must be larger than 0");
+        let sequence = self.sequence.as_str();
+        self.segments
+            .par_iter()
+            .fold(HashMap::new, |mut counts, segment| {
+                let bases = segment.sequence_str(sequence).to_ascii_uppercase();
+                for kmer in bases.as_bytes().windows(k) {
+                    if !kmer.iter().all(|x| matches!(x, b'A' | b'C' | b'G' | b'T')) {
+                        continue;
+                    }
+                    let mut kmer = kmer.to_vec();
+                    if canonical {
+                        let reverse: Vec<u8> = kmer
+                            .iter()
+                            .rev()
+                            .map(|x| match x {
+                                b'A' => b'T',
+                                b'C' => b'G',
+                                b'G' => b'C',
+                                _ => b'A',
+                            })
+                            .collect();
+                        kmer = kmer.min(reverse);
+                    }
+                    *counts.entry(kmer).or_insert(0) += 1;
+                }
+                counts
+            })
+            .reduce(HashMap::new, |a, b| {
+                let (mut a, b) = if a.len() < b.len() { (b, a) } else { (a, b) };
+                for (kmer, count) in b {
+                    *a.entry(kmer).or_insert(0) += count;
+                }
+                a
+            })
+    }
+
+    /// N50 and L50 of the segment lengths
+    ///
+    /// N50 is the length of the shortest segment of the largest segments covering half of the
+    /// total length, L50 the number of these segments. (0, 0) for an empty graph.
+    pub fn n50_l50(&self) -> (u32, usize) {
+        let mut lengths: Vec<u32> = self.segments.iter().map(|x| x.length).collect();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        let total = self.total_sequence_length();
+        let mut sum = 0;
+        for (i, length) in lengths.iter().enumerate() {
+            sum += *length as u64;
+            if sum * 2 >= total {
+                return (*length, i + 1);
+            }
+        }
+        (0, 0)
+    }
+
+    /// N50 of the segment lengths (see n50_l50)
+    pub fn n50(&self) -> u32 {
+        self.n50_l50().0
+    }
+
+    /// L50 of the segment lengths (see n50_l50)
+    pub fn l50(&self) -> usize {
+        self.n50_l50().1
+    }
+
+    /// Not 100%, but still okay
+    ///
+    /// Does not work with String and SeqIndex. An empty graph is not compact.
+    pub fn is_compact(&self) -> bool {
+        T::is_digit()
+            && !self.segments.is_empty()
+            && self.index_low == 1
+            && self.get_index_high() == self.segments.len()
+    }
+
+    /// Get a segment by id (both)
+    pub fn get_segment_by_id(&self, id: &T) -> &Segment<T, S> {
+        if self.is_digit {
+            self.get_segment_digit(id)
+        } else {
+            self.get_segment_nondigit(id)
+        }
+    }
+
+    /// Get a segment by id (digit)
+    ///
+    /// Requires a numeric id type (u32, u64, usize), panics otherwise. Use get_segment_by_id if unsure.
+    pub fn get_segment_digit(&self, id: &T) -> &Segment<T, S> {
+        assert!(
+            self.is_digit,
+            "get_segment_digit requires numeric segment ids, use get_segment_by_id"
+        );
+        let index = self.index_of_index[id.get_usize() - self.index_low];
+        &self.segments[index]
+    }
+
+    /// Get a segment by id (nondigit)
+    pub fn get_segment_nondigit(&self, id: &T) -> &Segment<T, S> {
+        &self.segments[self.segments.binary_search_by(|x| x.id.cmp(id)).unwrap()]
+    }
+
+    /// Segment with its sequence, None if it is not in the graph
+    ///
+    /// The sequence is taken from this graph, no need to pass the graph sequence separately
+    pub fn segment_view(&self, id: &T) -> Option<SegmentView<'_, T>> {
+        let segment = &self.segments[self.position_of(id)?];
+        Some(SegmentView {
+            id: &segment.id,
+            sequence: segment.sequence_str(&self.sequence),
+            length: segment.length,
+        })
+    }
+
+    /// Get a mutable segment by id, None if it is not in the graph
+    ///
+    /// Uses the same index as get_segment_by_id. Do not change the id, the index would be wrong.
+    pub fn get_segment_mut(&mut self, id: &T) -> Option<&mut Segment<T, S>> {
+        let position = self.position_of(id)?;
+        Some(&mut self.segments[position])
+    }
+
+    /// Sequence of a segment of this graph (also if stored inline)
+    pub fn segment_sequence<'a>(&'a self, segment: &'a Segment<T, S>) -> &'a str {
+        segment.sequence_str(&self.sequence)
+    }
+
+    /// Get a sequence by id
+    pub fn get_sequence_by_id(&self, id: &T) -> &str {
+        self.get_segment_by_id(id).sequence_str(&self.sequence)
+    }
+
+    /// Get a sequence by id (nondigit)
+    pub fn get_sequence_by_id_nondigit(&self, id: &T) -> &str {
+        self.get_segment_nondigit(id).sequence_str(&self.sequence)
+    }
+
+    /// Get a sequence by id (digit)
+    ///
+    /// Requires a numeric id type, panics otherwise (see get_segment_digit)
+    pub fn get_sequence_by_digit(&self, id: &T) -> &str {
+        self.get_segment_digit(id).sequence_str(&self.sequence)
+    }
+
+    /// Part of the sequence of a segment (0-based, end exclusive)
+    ///
+    /// Fails if the segment is not in the graph or the range is not within its sequence.
+    pub fn subsequence(&self, id: &T, start: usize, end: usize) -> Result<&str, GfaError> {
+        let position = self
+            .position_of(id)
+            .ok_or_else(|| GfaError::BadRange(format!("no segment {}", self.original_id(id))))?;
+        let sequence = self.segments[position].sequence_str(&self.sequence);
+        sequence.get(start..end).ok_or_else(|| {
+            GfaError::BadRange(format!(
+                "{}-{} is not within segment {} (length {})",
+                start,
+                end,
+                self.original_id(id),
+                sequence.len()
+            ))
+        })
+    }
+
+    /// Get a sequence by id in the given orientation
+    ///
+    /// The reverse strand (forward = false) is the reverse complement
+    pub fn get_sequence_oriented(&self, id: &T, forward: bool) -> Cow<'_, str> {
+        let sequence = self.get_sequence_by_id(id);
+        if forward {
+            Cow::Borrowed(sequence)
+        } else {
+            Cow::Owned(reverse_complement(sequence))
+        }
+    }
+
+    /// Dense position (0..len) of a node, which is its index in segments
+    ///
+    /// O(1) for numeric ids, binary search otherwise. Can be used to index per-node data in own vectors.
+    pub fn node_index(&self, id: &T) -> Option<usize> {
+        self.position_of(id)
+    }
+
+    /// Number of segments
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// The graph has no segments
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Node id at a dense index (0..len)
+    ///
+    /// Inverse of node_index. Panics if the index is out of range.
+    pub fn id_at(&self, dense: usize) -> &T {
+        &self.segments[dense].id
+    }
+
+    /// Position of a segment in `segments`, if the id exists
+    fn position_of(&self, id: &T) -> Option<usize> {
+        if self.is_digit {
+            let index = id.get_usize().checked_sub(self.index_low)?;
+            let position = *self.index_of_index.get(index)?;
+            (self.segments.get(position)?.id == *id).then_some(position)
+        } else {
+            self.segments.binary_search_by(|x| x.id.cmp(id)).ok()
+        }
+    }
+
+    /// Check that all links, paths, walks, containments and jumps reference existing segments
+    ///
+    /// Returns every missing reference
+    pub fn validate_references(&self) -> Vec<ValidationError<T>> {
+        let mut errors = Vec::new();
+        let mut check = |record: RecordType, index: usize, id: &T| {
+            if self.position_of(id).is_none() {
+                errors.push(ValidationError {
+                    record,
+                    index,
+                    id: id.clone(),
+                });
+            }
+        };
+        for (i, link) in self.links.iter().enumerate() {
+            check(RecordType::Link, i, &link.from);
+            check(RecordType::Link, i, &link.to);
+        }
+        for (i, path) in self.paths.iter().enumerate() {
+            path.nodes
+                .iter()
+                .for_each(|x| check(RecordType::Path, i, x));
+        }
+        for (i, walk) in self.walk.iter().enumerate() {
+            walk.walk_id
+                .iter()
+                .for_each(|x| check(RecordType::Walk, i, x));
+        }
+        for (i, containment) in self.containment.iter().enumerate() {
+            check(RecordType::Containment, i, &containment.container);
+            check(RecordType::Containment, i, &containment.contained);
+        }
+        for (i, jump) in self.jump.iter().enumerate() {
+            check(RecordType::Jump, i, &jump.from);
+            check(RecordType::Jump, i, &jump.to);
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            check(RecordType::Edge, i, &edge.from);
+            check(RecordType::Edge, i, &edge.to);
+        }
+        for (i, fragment) in self.fragments.iter().enumerate() {
+            check(RecordType::Fragment, i, &fragment.segment);
+        }
+        for (i, gap) in self.gaps.iter().enumerate() {
+            check(RecordType::Gap, i, &gap.from);
+            check(RecordType::Gap, i, &gap.to);
+        }
+        errors
+    }
+
+    /// Build the inverted index (segment -> paths)
+    ///
+    /// Needs to be rebuilt after paths or segments are modified
+    pub fn build_path_index(&mut self) {
+        let mut index = vec![Vec::new(); self.segments.len()];
+        for (path_id, path) in self.paths.iter().enumerate() {
+            for node in path.nodes.iter() {
+                if let Some(position) = self.position_of(node) {
+                    let entry: &mut Vec<usize> = &mut index[position];
+                    if entry.last() != Some(&path_id) {
+                        entry.push(path_id);
+                    }
+                }
+            }
+        }
+        self.path_index = index;
+    }
+
+    /// Get all paths which pass through a node
+    ///
+    /// Uses the inverted index if it was built (build_path_index), otherwise scans all paths
+    pub fn paths_through_node(&self, id: &T) -> Vec<&Path<T, S, U>> {
+        if self.path_index.is_empty() {
+            self.paths.iter().filter(|x| x.nodes.contains(id)).collect()
+        } else {
+            match self.position_of(id) {
+                Some(position) => self.path_index[position]
+                    .iter()
+                    .map(|&x| &self.paths[x])
+                    .collect(),
+                None => Vec::new(),
+            }
+        }
+    }
+
+    /// Build the index (path name -> path)
+    ///
+    /// Needs to be rebuilt after paths are modified
+    pub fn build_path_name_index(&mut self) {
+        let mut index = HashMap::with_capacity(self.paths.len());
+        for (i, path) in self.paths.iter().enumerate() {
+            index.entry(path.name.clone()).or_insert(i);
+        }
+        self.path_name_index = index;
+    }
+
+    /// Get a path by its name
+    ///
+    /// Uses the name index if it was built (build_path_name_index), otherwise scans all paths
+    pub fn get_path_by_name(&self, name: &str) -> Option<&Path<T, S, U>> {
+        if self.path_name_index.is_empty() {
+            self.paths.iter().find(|x| x.name == name)
+        } else {
+            self.path_name_index.get(name).map(|&i| &self.paths[i])
+        }
+    }
+
+    /// Rename all paths, f gets the old name and returns the new one
+    ///
+    /// The path name index is rebuilt if it exists.
+    pub fn rename_paths<F: FnMut(&str) -> String>(&mut self, mut f: F) {
+        for path in self.paths.iter_mut() {
+            path.name = f(&path.name);
+        }
+        if !self.path_name_index.is_empty() {
+            self.build_path_name_index();
+        }
+    }
+
+    /// Induced subgraph on a set of segment ids
+    ///
+    /// Links, jumps, containments, edges, fragments and gaps are kept if all their segments are in the set.
+    /// Paths and walks are only kept if all their nodes are in the set (partial paths are removed, not truncated).
+    /// The subgraph has its own graph sequence, containing only the data of the kept records.
+    pub fn subgraph(&self, ids: &HashSet<T>) -> Gfa<T, S, U>
+    where
+        T: Hash,
+        U: Clone,
+    {
+        let old = self.sequence.as_str();
+        let mut graph: Gfa<T, S, U> = Gfa::new();
+        graph.header = self.header.clone();
+        graph.interner = self.interner.clone();
+        let new = &mut graph.sequence;
+
+        for x in self.segments.iter().filter(|x| ids.contains(&x.id)) {
+            graph.segments.push(Segment {
+                id: x.id.relocate(old, new),
+                sequence: Opt::relocate(&x.sequence, old, new),
+                length: x.length,
+                opt: x.opt.relocate(old, new),
+                inline_sequence: x.inline_sequence.clone(),
+            });
+        }
+        for x in self
+            .links
+            .iter()
+            .filter(|x| ids.contains(&x.from) && ids.contains(&x.to))
+        {
+            graph.links.push(Link {
+                from: x.from.relocate(old, new),
+                to: x.to.relocate(old, new),
+                from_dir: x.from_dir,
+                to_dir: x.to_dir,
+                overlap: x.overlap.relocate(old, new),
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        for x in self
+            .paths
+            .iter()
+            .filter(|x| x.nodes.iter().all(|y| ids.contains(y)))
+        {
+            graph.paths.push(Path {
+                name: x.name.clone(),
+                dir: x.dir.clone(),
+                nodes: x.nodes.iter().map(|y| y.relocate(old, new)).collect(),
+                overlap: x.overlap.relocate(old, new),
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        for x in self
+            .walk
+            .iter()
+            .filter(|x| x.walk_id.iter().all(|y| ids.contains(y)))
+        {
+            graph.walk.push(Walk {
+                sample_id: x.sample_id.clone(),
+                hap_index: x.hap_index,
+                seq_id: x.seq_id.clone(),
+                seq_start: x.seq_start,
+                seq_end: x.seq_end,
+                walk_dir: x.walk_dir.clone(),
+                walk_id: x.walk_id.iter().map(|y| y.relocate(old, new)).collect(),
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        for x in self
+            .containment
+            .iter()
+            .filter(|x| ids.contains(&x.container) && ids.contains(&x.contained))
+        {
+            graph.containment.push(Containment {
+                container: x.container.relocate(old, new),
+                container_dir: x.container_dir,
+                contained: x.contained.relocate(old, new),
+                contained_dir: x.contained_dir,
+                pos: x.pos,
+                overlap: Opt::relocate(&x.overlap, old, new),
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        for x in self
+            .jump
+            .iter()
+            .filter(|x| ids.contains(&x.from) && ids.contains(&x.to))
+        {
+            graph.jump.push(Jump {
+                from: x.from.relocate(old, new),
+                from_dir: x.from_dir,
+                to: x.to.relocate(old, new),
+                to_dir: x.to_dir,
+                distance: x.distance,
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        for x in self
+            .edges
+            .iter()
+            .filter(|x| ids.contains(&x.from) && ids.contains(&x.to))
+        {
+            graph.edges.push(Edge {
+                id: x.id.clone(),
+                from: x.from.relocate(old, new),
+                from_dir: x.from_dir,
+                to: x.to.relocate(old, new),
+                to_dir: x.to_dir,
+                from_begin: x.from_begin,
+                from_end: x.from_end,
+                to_begin: x.to_begin,
+                to_end: x.to_end,
+                alignment: Opt::relocate(&x.alignment, old, new),
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        for x in self.fragments.iter().filter(|x| ids.contains(&x.segment)) {
+            graph.fragments.push(Fragment {
+                segment: x.segment.relocate(old, new),
+                external: x.external.clone(),
+                external_dir: x.external_dir,
+                segment_begin: x.segment_begin,
+                segment_end: x.segment_end,
+                fragment_begin: x.fragment_begin,
+                fragment_end: x.fragment_end,
+                alignment: Opt::relocate(&x.alignment, old, new),
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        for x in self
+            .gaps
+            .iter()
+            .filter(|x| ids.contains(&x.from) && ids.contains(&x.to))
+        {
+            graph.gaps.push(Gap {
+                id: x.id.clone(),
+                from: x.from.relocate(old, new),
+                from_dir: x.from_dir,
+                to: x.to.relocate(old, new),
+                to_dir: x.to_dir,
+                distance: x.distance,
+                variance: x.variance,
+                opt: x.opt.relocate(old, new),
+            });
+        }
+        graph.build_index();
+        graph
+    }
+
+    /// Convert the graph to dense numeric ids (1..=number of segments)
+    ///
+    /// Segments are numbered in the order of their original ids (sorted as strings), all records are
+    /// rewritten. Ids which are referenced but are not a segment get the following numbers. Returns the
+    /// graph and the mapping (original id -> new id). Useful for string ids (String, SeqIndex, InternedId).
+    pub fn to_numeric(mut self) -> (Gfa<u32, S, U>, HashMap<String, u32>) {
+        let sequence = std::mem::take(&mut self.sequence);
+        let interner = std::mem::take(&mut self.interner);
+        let mut names: Vec<String> = self
+            .segments
+            .iter()
+            .map(|x| x.id.id_string(&sequence, &interner))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        let mut mapping: HashMap<String, u32> = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (name, i as u32 + 1))
+            .collect();
+        let mut id = |x: &T| {
+            let next = mapping.len() as u32 + 1;
+            *mapping
+                .entry(x.id_string(&sequence, &interner))
+                .or_insert(next)
+        };
+
+        let mut graph: Gfa<u32, S, U> = Gfa::new();
+        graph.header = std::mem::take(&mut self.header);
+        graph.unknown_lines = std::mem::take(&mut self.unknown_lines);
+        graph.segment_order = std::mem::take(&mut self.segment_order);
+        for x in self.segments.drain(..) {
+            graph.segments.push(Segment {
+                id: id(&x.id),
+                sequence: x.sequence,
+                length: x.length,
+                opt: x.opt,
+                inline_sequence: x.inline_sequence,
+            });
+        }
+        for x in self.links.drain(..) {
+            graph.links.push(Link {
+                from: id(&x.from),
+                from_dir: x.from_dir,
+                to: id(&x.to),
+                to_dir: x.to_dir,
+                overlap: x.overlap,
+                opt: x.opt,
+            });
+        }
+        for x in self.paths.drain(..) {
+            graph.paths.push(Path {
+                name: x.name,
+                dir: x.dir,
+                nodes: x.nodes.iter().map(&mut id).collect(),
+                overlap: x.overlap,
+                opt: x.opt,
+            });
+        }
+        for x in self.walk.drain(..) {
+            graph.walk.push(Walk {
+                sample_id: x.sample_id,
+                hap_index: x.hap_index,
+                seq_id: x.seq_id,
+                seq_start: x.seq_start,
+                seq_end: x.seq_end,
+                walk_dir: x.walk_dir,
+                walk_id: x.walk_id.iter().map(&mut id).collect(),
+                opt: x.opt,
+            });
+        }
+        for x in self.containment.drain(..) {
+            graph.containment.push(Containment {
+                container: id(&x.container),
+                container_dir: x.container_dir,
+                contained: id(&x.contained),
+                contained_dir: x.contained_dir,
+                pos: x.pos,
+                overlap: x.overlap,
+                opt: x.opt,
+            });
+        }
+        for x in self.jump.drain(..) {
+            graph.jump.push(Jump {
+                from: id(&x.from),
+                from_dir: x.from_dir,
+                to: id(&x.to),
+                to_dir: x.to_dir,
+                distance: x.distance,
+                opt: x.opt,
+            });
+        }
+        for x in self.edges.drain(..) {
+            graph.edges.push(Edge {
+                id: x.id,
+                from: id(&x.from),
+                from_dir: x.from_dir,
+                to: id(&x.to),
+                to_dir: x.to_dir,
+                from_begin: x.from_begin,
+                from_end: x.from_end,
+                to_begin: x.to_begin,
+                to_end: x.to_end,
+                alignment: x.alignment,
+                opt: x.opt,
+            });
+        }
+        for x in self.fragments.drain(..) {
+            graph.fragments.push(Fragment {
+                segment: id(&x.segment),
+                external: x.external,
+                external_dir: x.external_dir,
+                segment_begin: x.segment_begin,
+                segment_end: x.segment_end,
+                fragment_begin: x.fragment_begin,
+                fragment_end: x.fragment_end,
+                alignment: x.alignment,
+                opt: x.opt,
+            });
+        }
+        for x in self.gaps.drain(..) {
+            graph.gaps.push(Gap {
+                id: x.id,
+                from: id(&x.from),
+                from_dir: x.from_dir,
+                to: id(&x.to),
+                to_dir: x.to_dir,
+                distance: x.distance,
+                variance: x.variance,
+                opt: x.opt,
+            });
+        }
+        // Sequences and optional fields still point into the same graph sequence
+        graph.sequence = sequence;
+        graph.build_index();
+        if !self.path_name_index.is_empty() {
+            graph.build_path_name_index();
+        }
+        (graph, mapping)
+    }
+
+    /// Subgraph of a single path
+    ///
+    /// Contains the nodes of the path, the links between consecutive path nodes and the path itself.
+    /// Returns None if there is no path with this name.
+    pub fn path_subgraph(&self, path_name: &str) -> Option<Gfa<T, S, U>>
+    where
+        T: Hash,
+        U: Clone,
+    {
+        let path = self.get_path_by_name(path_name)?;
+        let mut graph = self.subgraph(&path.nodes.iter().cloned().collect());
+
+        // Steps of the path, links can be used in both orientations
+        let mut steps = HashSet::new();
+        for i in 1..path.nodes.len() {
+            let (from, from_dir) = (&path.nodes[i - 1], path.dir[i - 1]);
+            let (to, to_dir) = (&path.nodes[i], path.dir[i]);
+            steps.insert((from, from_dir, to, to_dir));
+            steps.insert((to, !to_dir, from, !from_dir));
+        }
+        graph
+            .links
+            .retain(|x| steps.contains(&(&x.from, x.from_dir, &x.to, x.to_dir)));
+        graph.paths.retain(|x| x.name == path_name);
+        graph.paths.truncate(1);
+        graph.walk.clear();
+        graph.containment.clear();
+        graph.jump.clear();
+        graph.edges.clear();
+        graph.fragments.clear();
+        graph.gaps.clear();
+        Some(graph)
+    }
+
+    /// Compacted graph, maximal non-branching chains of segments are merged into unitigs
+    ///
+    /// Two oriented segments are merged if the link between them is the only link on both sides.
+    /// A unitig is named after its first segment, its sequence is the concatenation of the oriented
+    /// segment sequences (overlaps are ignored). The other links are kept (in unitig orientation),
+    /// optional fields of merged segments are dropped. Paths, walks and all other records are not copied.
+    /// Also returns the position of every old segment: id -> (unitig id, offset in bp, forward).
+    pub fn compact_unitigs(&self) -> (Gfa<T, S, U>, UnitigMap<T>)
+    where
+        T: Hash,
+        U: Clone,
+    {
+        let adjacency = self.adjacency();
+        // The only link of a handle, if it is also the only link entering the next handle
+        let next = |node: usize| match adjacency.outgoing[node].as_slice() {
+            [(link, next)] if adjacency.incoming[*next].len() == 1 && next / 2 != node / 2 => {
+                Some((*link, *next))
+            }
+            _ => None,
+        };
+
+        let mut visited = vec![false; self.segments.len()];
+        let mut walked = vec![usize::MAX; self.segments.len()];
+        let mut internal = vec![false; self.links.len()];
+        let mut unitigs = Vec::new();
+        for start in 0..self.segments.len() {
+            if visited[start] {
+                continue;
+            }
+            // Go back to the first handle of the chain (stops in circular chains)
+            let mut first = handle(start, true);
+            walked[start] = start;
+            while let Some((_, previous)) = next(first ^ 1) {
+                let previous = previous ^ 1;
+                if walked[previous / 2] == start || visited[previous / 2] {
+                    break;
+                }
+                walked[previous / 2] = start;
+                first = previous;
+            }
+            let mut unitig = vec![first];
+            visited[first / 2] = true;
+            let mut node = first;
+            while let Some((link, following)) = next(node) {
+                if visited[following / 2] {
+                    break;
+                }
+                internal[link] = true;
+                visited[following / 2] = true;
+                unitig.push(following);
+                node = following;
+            }
+            unitigs.push(unitig);
+        }
+
+        let old = self.sequence.as_str();
+        let mut graph: Gfa<T, S, U> = Gfa::new();
+        graph.header = self.header.clone();
+        graph.interner = self.interner.clone();
+        let new = &mut graph.sequence;
+        let mut mapping = HashMap::with_capacity(self.segments.len());
+        // Unitig index and orientation of each old segment
+        let mut unitig_of = vec![(0, true); self.segments.len()];
+        let mut ids = Vec::with_capacity(unitigs.len());
+        for (i, unitig) in unitigs.iter().enumerate() {
+            let first = &self.segments[unitig[0] / 2];
+            let id = first.id.relocate(old, new);
+            let mut sequence = String::new();
+            let mut length = 0;
+            for &node in unitig.iter() {
+                let segment = &self.segments[node / 2];
+                let forward = node % 2 == 0;
+                mapping.insert(segment.id.clone(), (id.clone(), length as usize, forward));
+                unitig_of[node / 2] = (i, forward);
+                if forward {
+                    sequence.push_str(segment.sequence_str(old));
+                } else {
+                    sequence.push_str(&reverse_complement(segment.sequence_str(old)));
+                }
+                length += segment.length;
+            }
+            let opt = if unitig.len() == 1 {
+                first.opt.relocate(old, new)
+            } else {
+                S::parse1(None, new)
+            };
+            graph.segments.push(Segment {
+                id: id.clone(),
+                sequence: SeqIndex::parse1(&sequence, new),
+                length,
+                opt,
+                inline_sequence: None,
+            });
+            ids.push(id);
+        }
+
+        for (i, link) in self.links.iter().enumerate() {
+            if internal[i] {
+                continue;
+            }
+            if let (Some(from), Some(to)) =
+                (self.position_of(&link.from), self.position_of(&link.to))
+            {
+                let (from, from_forward) = unitig_of[from];
+                let (to, to_forward) = unitig_of[to];
+                graph.links.push(Link {
+                    from: ids[from].clone(),
+                    to: ids[to].clone(),
+                    from_dir: link.from_dir == from_forward,
+                    to_dir: link.to_dir == to_forward,
+                    overlap: link.overlap.relocate(old, new),
+                    opt: link.opt.relocate(old, new),
+                });
+            }
+        }
+        graph.build_index();
+        (graph, mapping)
+    }
+
+    /// Keep only the segments for which pred(segment, sequence) is true
+    ///
+    /// Links, containments, jumps, edges, fragments and gaps with a removed segment are removed.
+    /// Paths and walks are kept, but the steps on removed segments are removed from them.
+    pub fn retain_segments<F: Fn(&Segment<T, S>, &str) -> bool>(&mut self, pred: F) {
+        let sequence = &self.sequence;
+        let interner = &self.interner;
+        let mut removed_digit = HashSet::new();
+        let mut removed_names = HashSet::new();
+        let mask: Vec<bool> = self
+            .segments
+            .iter()
+            .map(|x| {
+                let keep = pred(x, x.sequence_str(sequence));
+                if !keep {
+                    if T::is_digit() {
+                        removed_digit.insert(x.id.get_usize());
+                    } else {
+                        removed_names.insert(x.id.id_string(sequence, interner));
+                    }
+                }
+                keep
+            })
+            .collect();
+        retain_mask(&mut self.segments, &mask);
+        if self.segment_order.len() == mask.len() {
+            retain_mask(&mut self.segment_order, &mask);
+        }
+        if removed_digit.is_empty() && removed_names.is_empty() {
+            return;
+        }
+
+        // Compare by number or by name, since SeqIndex ids differ for every occurrence
+        let keep = |id: &T| {
+            if T::is_digit() {
+                !removed_digit.contains(&id.get_usize())
+            } else {
+                !removed_names.contains(&id.id_string(sequence, interner))
+            }
+        };
+        self.links.retain(|x| keep(&x.from) && keep(&x.to));
+        for path in self.paths.iter_mut() {
+            let mask: Vec<bool> = path.nodes.iter().map(keep).collect();
+            retain_mask(&mut path.nodes, &mask);
+            retain_mask(&mut path.dir, &mask);
+        }
+        for walk in self.walk.iter_mut() {
+            let mask: Vec<bool> = walk.walk_id.iter().map(keep).collect();
+            retain_mask(&mut walk.walk_id, &mask);
+            retain_mask(&mut walk.walk_dir, &mask);
+        }
+        self.containment
+            .retain(|x| keep(&x.container) && keep(&x.contained));
+        self.jump.retain(|x| keep(&x.from) && keep(&x.to));
+        self.edges.retain(|x| keep(&x.from) && keep(&x.to));
+        self.fragments.retain(|x| keep(&x.segment));
+        self.gaps.retain(|x| keep(&x.from) && keep(&x.to));
+        self.build_index();
+    }
+
+    /// Same segments, links, paths and walks, independent of their order
+    ///
+    /// Records are compared by original ids and text (not by position in the graph sequence), a link
+    /// equals its reverse complement (a+ -> b+ is b- -> a-, the overlap is not reversed). Other
+    /// record types and the header are not compared.
+    pub fn semantically_eq(&self, other: &Self) -> bool {
+        fn sorted<K: Ord>(mut records: Vec<K>) -> Vec<K> {
+            records.sort_unstable();
+            records
+        }
+        let segments = |g: &Self| {
+            sorted(
+                g.segments
+                    .iter()
+                    .map(|x| {
+                        (
+                            g.original_id(&x.id),
+                            x.sequence_str(&g.sequence).to_string(),
+                            x.length,
+                            x.opt.as_str(&g.sequence).to_string(),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+        let links = |g: &Self| {
+            sorted(
+                g.links
+                    .iter()
+                    .map(|x| {
+                        let (from, to) = (g.original_id(&x.from), g.original_id(&x.to));
+                        let ends = std::cmp::min(
+                            (from.clone(), x.from_dir, to.clone(), x.to_dir),
+                            (to, !x.to_dir, from, !x.from_dir),
+                        );
+                        (
+                            ends,
+                            x.overlap.as_str(&g.sequence).to_string(),
+                            x.opt.as_str(&g.sequence).to_string(),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+        let paths = |g: &Self| {
+            sorted(
+                g.paths
+                    .iter()
+                    .map(|x| {
+                        (
+                            x.name.clone(),
+                            x.nodes.iter().map(|y| g.original_id(y)).collect::<Vec<_>>(),
+                            x.dir.clone(),
+                            x.overlap.as_str(&g.sequence).to_string(),
+                            x.opt.as_str(&g.sequence).to_string(),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+        let walks = |g: &Self| {
+            sorted(
+                g.walk
+                    .iter()
+                    .map(|x| {
+                        (
+                            (x.sample_id.clone(), x.hap_index, x.seq_id.clone()),
+                            (x.seq_start, x.seq_end),
+                            x.walk_id
+                                .iter()
+                                .map(|y| g.original_id(y))
+                                .collect::<Vec<_>>(),
+                            x.walk_dir.clone(),
+                            x.opt.as_str(&g.sequence).to_string(),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+        segments(self) == segments(other)
+            && links(self) == links(other)
+            && paths(self) == paths(other)
+            && walks(self) == walks(other)
+    }
+
+    /// Comparable key of an id (number for numeric ids, otherwise the name)
+    fn id_key(&self, id: &T) -> (usize, String) {
+        if T::is_digit() {
+            (id.get_usize(), String::new())
+        } else {
+            (0, self.original_id(id))
+        }
+    }
+
+    /// Links which are equal to an earlier link (same ends and orientations)
+    ///
+    /// A link equals its reverse complement (a+ -> b+ is b- -> a-, the overlap is not reversed).
+    /// If compare_tags is true, links with different overlap or optional fields are not equal.
+    fn parallel_links(&self, compare_tags: bool) -> Vec<bool> {
+        let mut seen = HashSet::new();
+        self.links
+            .iter()
+            .map(|x| {
+                let tags = if compare_tags {
+                    (
+                        x.overlap.as_str(&self.sequence),
+                        x.opt.as_str(&self.sequence),
+                    )
+                } else {
+                    ("", "")
+                };
+                let (from, to) = (self.id_key(&x.from), self.id_key(&x.to));
+                let key = std::cmp::min(
+                    (from.clone(), x.from_dir, to.clone(), x.to_dir),
+                    (to, !x.to_dir, from, !x.from_dir),
+                );
+                !seen.insert((key, tags))
+            })
+            .collect()
+    }
+
+    /// Number of duplicated links (see dedup_links)
+    pub fn count_parallel_links(&self, compare_tags: bool) -> usize {
+        self.parallel_links(compare_tags)
+            .iter()
+            .filter(|x| **x)
+            .count()
+    }
+
+    /// Remove duplicated links, the first link is kept
+    ///
+    /// A link and its reverse complement (a+ -> b+ and b- -> a-) are duplicates.
+    /// If compare_tags is true, links with different overlap or optional fields are not duplicates.
+    /// The order of the remaining links is unchanged. Returns the number of removed links.
+    pub fn dedup_links(&mut self, compare_tags: bool) -> usize {
+        let duplicated = self.parallel_links(compare_tags);
+        let keep: Vec<bool> = duplicated.iter().map(|x| !x).collect();
+        retain_mask(&mut self.links, &keep);
+        self.adjacency = Adjacency::default();
+        duplicated.iter().filter(|x| **x).count()
+    }
+
+    /// Sort the links by (from, from_dir, to, to_dir), has_link will use binary search afterwards
+    ///
+    /// If links are changed directly (not using add_link), call sort_links again
+    pub fn sort_links(&mut self) {
+        self.links.sort_by(|a, b| {
+            (&a.from, a.from_dir, &a.to, a.to_dir).cmp(&(&b.from, b.from_dir, &b.to, b.to_dir))
+        });
+        self.links_sorted = true;
+        self.adjacency = Adjacency::default();
+    }
+
+    /// Sort the segments by a custom key (e.g. |x| std::cmp::Reverse(x.length)) and update the index
+    ///
+    /// Supports numeric ids (u32, u64, usize, i32, i64) and InternedId. String and SeqIndex ids are
+    /// found by binary search over the sorted ids, for them GfaError::NonNumericIds is returned and
+    /// the graph is not changed. Sorting is stable. Functions
+    /// which rebuild the index (e.g. add_segment, merge, canonicalize) sort the segments by id again.
+    pub fn sort_segments_by<K: Ord, F: FnMut(&Segment<T, S>) -> K>(
+        &mut self,
+        mut key: F,
+    ) -> Result<(), GfaError> {
+        if !self.is_digit {
+            return Err(GfaError::NonNumericIds);
+        }
+        self.fill_segment_order();
+        let order = std::mem::take(&mut self.segment_order);
+        let mut segments: Vec<(Segment<T, S>, usize)> = std::mem::take(&mut self.segments)
+            .into_iter()
+            .zip(order)
+            .collect();
+        segments.sort_by_cached_key(|x| key(&x.0));
+        (self.segments, self.segment_order) = segments.into_iter().unzip();
+
+        let ids = self.segments.iter().map(|x| x.id.get_usize());
+        let (low, high) = (ids.clone().min().unwrap_or(0), ids.max().unwrap_or(0));
+        self.index_low = low;
+        self.index_of_index = Vec::new();
+        if !self.segments.is_empty() {
+            let mut index = vec![0; high - low + 1];
+            for (position, x) in self.segments.iter().enumerate() {
+                index[x.id.get_usize() - low] = position;
+            }
+            self.index_of_index = index;
+        }
+        self.path_index = Vec::new();
+        self.adjacency = Adjacency::default();
+        Ok(())
+    }
+
+    /// Bring the records in a fixed order, for reproducible output
+    ///
+    /// Segments are sorted by id, links like in sort_links, paths by name and walks by
+    /// (sample, haplotype, sequence id, start). Sorting is stable and uses the order of the id type
+    /// (SeqIndex ids are ordered by their position in the graph sequence).
+    pub fn canonicalize(&mut self) {
+        self.build_index();
+        self.sort_links();
+        self.paths.sort_by(|a, b| a.name.cmp(&b.name));
+        self.walk.sort_by(|a, b| {
+            (&a.sample_id, a.hap_index, &a.seq_id, a.seq_start).cmp(&(
+                &b.sample_id,
+                b.hap_index,
+                &b.seq_id,
+                b.seq_start,
+            ))
+        });
+        if !self.path_name_index.is_empty() {
+            self.build_path_name_index();
+        }
+    }
+
+    /// Check if a link exists (in this or the reverse complement orientation)
+    ///
+    /// Binary search if the links are sorted (sort_links), otherwise a linear scan.
+    /// Does not work with SeqIndex ids.
+    pub fn has_link(&self, from: &T, from_dir: bool, to: &T, to_dir: bool) -> bool {
+        let find = |key: (&T, bool, &T, bool)| {
+            if self.links_sorted {
+                self.links
+                    .binary_search_by(|x| (&x.from, x.from_dir, &x.to, x.to_dir).cmp(&key))
+                    .is_ok()
+            } else {
+                self.links
+                    .iter()
+                    .any(|x| (&x.from, x.from_dir, &x.to, x.to_dir) == key)
+            }
+        };
+        find((from, from_dir, to, to_dir)) || find((to, !to_dir, from, !from_dir))
+    }
+
+    /// Build the oriented adjacency index (segment -> links)
+    ///
+    /// Needs to be rebuilt after links are modified directly (add_link, sort_links and dedup_links reset it).
+    /// Links with missing segments are ignored.
+    pub fn build_adjacency(&mut self) {
+        self.adjacency = self.adjacency_from_links();
+    }
+
+    /// Build the oriented adjacency index including the jumps (J lines)
+    ///
+    /// Jumps are kept apart from the links, they are not sequence-adjacent and are only used by
+    /// jump_neighbors. Like links, every jump is stored in both orientations.
+    pub fn build_adjacency_with_jumps(&mut self) {
+        let mut adjacency = self.adjacency_from_links();
+        adjacency.jumps = vec![Vec::new(); 2 * self.segments.len()];
+        for (i, jump) in self.jump.iter().enumerate() {
+            if let (Some(from), Some(to)) =
+                (self.position_of(&jump.from), self.position_of(&jump.to))
+            {
+                let (from, to) = (handle(from, jump.from_dir), handle(to, jump.to_dir));
+                adjacency.jumps[from].push((i, to));
+                if (to ^ 1, from ^ 1) != (from, to) {
+                    adjacency.jumps[to ^ 1].push((i, from ^ 1));
+                }
+            }
+        }
+        self.adjacency = adjacency;
+    }
+
+    /// Segments connected to a segment by a jump, with the jump distance (None if unknown)
+    ///
+    /// Both ends of the jumps are considered, in the order of the jumps. Uses the index of
+    /// build_adjacency_with_jumps if it was built, otherwise all jumps are checked.
+    pub fn jump_neighbors(&self, id: &T) -> Vec<(T, Option<i64>)> {
+        let other = |i: usize| {
+            let jump = &self.jump[i];
+            let neighbour = if &jump.from == id {
+                &jump.to
+            } else {
+                &jump.from
+            };
+            (neighbour.clone(), jump.distance)
+        };
+        if self.adjacency.jumps.is_empty() {
+            return (0..self.jump.len())
+                .filter(|&i| &self.jump[i].from == id || &self.jump[i].to == id)
+                .map(other)
+                .collect();
+        }
+        let position = match self.position_of(id) {
+            Some(position) => position,
+            None => return Vec::new(),
+        };
+        let mut jumps: Vec<usize> = self.adjacency.jumps[handle(position, true)]
+            .iter()
+            .chain(self.adjacency.jumps[handle(position, false)].iter())
+            .map(|x| x.0)
+            .collect();
+        jumps.sort_unstable();
+        jumps.dedup();
+        jumps.into_iter().map(other).collect()
+    }
+
+    fn adjacency_from_links(&self) -> Adjacency {
+        let mut adjacency = Adjacency {
+            outgoing: vec![Vec::new(); 2 * self.segments.len()],
+            incoming: vec![Vec::new(); 2 * self.segments.len()],
+            jumps: Vec::new(),
+        };
+        for (i, link) in self.links.iter().enumerate() {
+            if let (Some(from), Some(to)) =
+                (self.position_of(&link.from), self.position_of(&link.to))
+            {
+                let (from, to) = (handle(from, link.from_dir), handle(to, link.to_dir));
+                adjacency.add(i, from, to);
+                // A link to its own reverse complement (e.g. 1+ -> 1-) is only stored once
+                if (to ^ 1, from ^ 1) != (from, to) {
+                    adjacency.add(i, to ^ 1, from ^ 1);
+                }
+            }
+        }
+        adjacency
+    }
+
+    /// The adjacency index if it was built (build_adjacency), otherwise a temporary one
+    fn adjacency(&self) -> Cow<'_, Adjacency> {
+        if self.adjacency.outgoing.is_empty() {
+            Cow::Owned(self.adjacency_from_links())
+        } else {
+            Cow::Borrowed(&self.adjacency)
+        }
+    }
+
+    /// Topological order of the segments
+    ///
+    /// Sorts both orientations of all segments (handles) using the adjacency index, the order of the
+    /// forward handles is returned. A link a+ -> b+ puts a before b, a- -> b- puts b before a.
+    /// If the graph has a cycle, one example cycle is returned.
+    pub fn topological_sort(&self) -> Result<Vec<T>, CycleError<T>> {
+        let adjacency = self.adjacency();
+        let mut in_degree: Vec<usize> = adjacency.incoming.iter().map(|x| x.len()).collect();
+        let mut stack: Vec<usize> = (0..in_degree.len())
+            .rev()
+            .filter(|&x| in_degree[x] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(in_degree.len());
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            for &(_, next) in adjacency.outgoing[node].iter() {
+                in_degree[next] -= 1;
+                if in_degree[next] == 0 {
+                    stack.push(next);
+                }
+            }
+        }
+
+        if order.len() < in_degree.len() {
+            // Every remaining handle has a remaining predecessor, go backwards until one repeats
+            let mut step = vec![usize::MAX; in_degree.len()];
+            let mut visited = Vec::new();
+            let mut node = (0..in_degree.len()).find(|&x| in_degree[x] > 0).unwrap();
+            while step[node] == usize::MAX {
+                step[node] = visited.len();
+                visited.push(node);
+                node = adjacency.incoming[node]
+                    .iter()
+                    .map(|x| x.1)
+                    .find(|&x| in_degree[x] > 0)
+                    .unwrap();
+            }
+            let cycle = visited[step[node]..]
+                .iter()
+                .rev()
+                .map(|&x| (self.segments[x / 2].id.clone(), x % 2 == 0))
+                .collect();
+            return Err(CycleError { cycle });
+        }
+        Ok(order
+            .into_iter()
+            .filter(|x| x % 2 == 0)
+            .map(|x| self.segments[x / 2].id.clone())
+            .collect())
+    }
+
+    /// Oriented neighbours reachable by one link from a node traversed in the given orientation
+    ///
+    /// Links are bidirected: L a - b + can be traversed as a- -> b+ and as b- -> a+.
+    /// Returns (neighbour, forward) pairs, empty if the node is not in the graph.
+    /// Build the adjacency index (build_adjacency) when calling this for many nodes.
+    pub fn traverse_from(&self, node: &T, entering_forward: bool) -> Vec<(T, bool)> {
+        match self.position_of(node) {
+            Some(position) => self.adjacency().outgoing[handle(position, entering_forward)]
+                .iter()
+                .map(|&(_, x)| (self.segments[x / 2].id.clone(), x % 2 == 0))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Shortest path between two segments (fewest links), following link orientations
+    ///
+    /// Starts at both orientations of from and ends at any orientation of to. Returns the segments of
+    /// the path (incl. from and to), None if to is not reachable or a segment is not in the graph.
+    pub fn shortest_path(&self, from: &T, to: &T) -> Option<Vec<T>> {
+        self.shortest_path_limited(from, to, usize::MAX)
+    }
+
+    /// Shortest path (see shortest_path) with at most max_links links
+    ///
+    /// The search stops after max_links steps, which bounds the work on large graphs
+    pub fn shortest_path_limited(&self, from: &T, to: &T, max_links: usize) -> Option<Vec<T>> {
+        let (start, end) = (self.position_of(from)?, self.position_of(to)?);
+        if start == end {
+            return Some(vec![from.clone()]);
+        }
+        let adjacency = self.adjacency();
+        let mut parent = vec![usize::MAX; adjacency.outgoing.len()];
+        let mut queue = std::collections::VecDeque::new();
+        for forward in [true, false].iter() {
+            parent[handle(start, *forward)] = handle(start, *forward);
+            queue.push_back((handle(start, *forward), 0));
+        }
+        while let Some((node, depth)) = queue.pop_front() {
+            if depth == max_links {
+                continue;
+            }
+            for &(_, next) in adjacency.outgoing[node].iter() {
+                if parent[next] != usize::MAX {
+                    continue;
+                }
+                parent[next] = node;
+                if next / 2 == end {
+                    return Some(self.path_from_parents(&parent, next));
+                }
+                queue.push_back((next, depth + 1));
+            }
+        }
+        None
+    }
+
+    /// Shortest path between two segments by sequence length (Dijkstra)
+    ///
+    /// Same traversal as shortest_path, but every segment costs its length. Returns the segments of
+    /// the path and the total length of all its segments (incl. from and to, overlaps are ignored).
+    pub fn shortest_path_bp(&self, from: &T, to: &T) -> Option<(Vec<T>, u64)> {
+        let (start, end) = (self.position_of(from)?, self.position_of(to)?);
+        let length = |handle: usize| self.segments[handle / 2].length as u64;
+        if start == end {
+            return Some((vec![from.clone()], length(handle(start, true))));
+        }
+        let adjacency = self.adjacency();
+        let mut distance = vec![u64::MAX; adjacency.outgoing.len()];
+        let mut parent = vec![usize::MAX; adjacency.outgoing.len()];
+        let mut heap = std::collections::BinaryHeap::new();
+        for forward in [true, false].iter() {
+            let node = handle(start, *forward);
+            distance[node] = length(node);
+            parent[node] = node;
+            heap.push(std::cmp::Reverse((distance[node], node)));
+        }
+        while let Some(std::cmp::Reverse((current, node))) = heap.pop() {
+            if current > distance[node] {
+                continue;
+            }
+            if node / 2 == end {
+                return Some((self.path_from_parents(&parent, node), current));
+            }
+            for &(_, next) in adjacency.outgoing[node].iter() {
+                let candidate = current + length(next);
+                if candidate < distance[next] {
+                    distance[next] = candidate;
+                    parent[next] = node;
+                    heap.push(std::cmp::Reverse((candidate, next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Segments from the start handle (its own parent) to handle
+    fn path_from_parents(&self, parent: &[usize], mut handle: usize) -> Vec<T> {
+        let mut path = vec![self.segments[handle / 2].id.clone()];
+        while parent[handle] != handle {
+            handle = parent[handle];
+            path.push(self.segments[handle / 2].id.clone());
+        }
+        path.reverse();
+        path
+    }
+
+    /// Links starting at a segment (link.from == id)
+    ///
+    /// O(degree) if the adjacency index was built (build_adjacency), otherwise scans all links.
+    /// The index skips links with missing segments.
+    pub fn links_from<'a>(&'a self, id: &'a T) -> impl Iterator<Item = &'a Link<T, S, U>> + 'a {
+        self.links_touching(id, true)
+    }
+
+    /// Links ending at a segment (link.to == id)
+    ///
+    /// O(degree) if the adjacency index was built (build_adjacency), otherwise scans all links.
+    /// The index skips links with missing segments.
+    pub fn links_to<'a>(&'a self, id: &'a T) -> impl Iterator<Item = &'a Link<T, S, U>> + 'a {
+        self.links_touching(id, false)
+    }
+
+    fn links_touching<'a>(
+        &'a self,
+        id: &'a T,
+        from: bool,
+    ) -> Box<dyn Iterator<Item = &'a Link<T, S, U>> + 'a> {
+        fn end<T: SampleType, S: Opt, U: Opt>(link: &Link<T, S, U>, from: bool) -> (&T, bool) {
+            if from {
+                (&link.from, link.from_dir)
+            } else {
+                (&link.to, link.to_dir)
+            }
+        }
+        if self.adjacency.outgoing.is_empty() {
+            return Box::new(self.links.iter().filter(move |x| end(x, from).0 == id));
+        }
+        let position = match self.position_of(id) {
+            Some(position) => position,
+            None => return Box::new(std::iter::empty()),
+        };
+        let entries = if from {
+            &self.adjacency.outgoing
+        } else {
+            &self.adjacency.incoming
+        };
+        // Both orientations of every link are stored, keep only the one as written in the file
+        Box::new([true, false].iter().flat_map(move |&forward| {
+            entries[handle(position, forward)]
+                .iter()
+                .map(move |&(i, _)| &self.links[i])
+                .filter(move |x| end(x, from) == (id, forward))
+        }))
+    }
+
+    /// Links from a segment to itself (e.g. L 5 + 5 +, also L 5 + 5 -)
+    ///
+    /// Does not work with SeqIndex ids.
+    pub fn self_loops(&self) -> Vec<&Link<T, S, U>> {
+        self.links.iter().filter(|x| x.from == x.to).collect()
+    }
+
+    /// Tips of the graph (sources, sinks)
+    ///
+    /// Sources have no link entering the forward node (start side), sinks have no link leaving
+    /// the forward node (end side). Links in both orientations are considered. Isolated nodes are in both.
+    pub fn tips(&self) -> (Vec<T>, Vec<T>) {
+        let adjacency = self.adjacency();
+        let mut sources = Vec::new();
+        let mut sinks = Vec::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            if adjacency.incoming[handle(i, true)].is_empty() {
+                sources.push(segment.id.clone());
+            }
+            if adjacency.outgoing[handle(i, true)].is_empty() {
+                sinks.push(segment.id.clone());
+            }
+        }
+        (sources, sinks)
+    }
+
+    /// Check if the oriented link graph has no cycle
+    ///
+    /// Iterative DFS over all handles (every component), false if a back-edge is found.
+    pub fn is_acyclic(&self) -> bool {
+        // 0 = new, 1 = on the stack, 2 = done
+        let adjacency = self.adjacency();
+        let mut state = vec![0u8; adjacency.outgoing.len()];
+        for start in 0..state.len() {
+            if state[start] != 0 {
+                continue;
+            }
+            state[start] = 1;
+            let mut stack = vec![(start, 0)];
+            while let Some((node, next)) = stack.last_mut() {
+                match adjacency.outgoing[*node].get(*next) {
+                    Some(&(_, neighbour)) => {
+                        *next += 1;
+                        match state[neighbour] {
+                            0 => {
+                                state[neighbour] = 1;
+                                stack.push((neighbour, 0));
+                            }
+                            1 => return false,
+                            _ => {}
+                        }
+                    }
+                    None => {
+                        state[*node] = 2;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    #[cfg(feature = "bitset")]
+    /// Neighbours of a segment as a bitset over the segment positions (see node_index)
+    ///
+    /// Contains all segments linked to the segment, in any direction and orientation (itself for a
+    /// self-loop). Uses the adjacency index. Empty if the segment is not in the graph.
+    pub fn neighbor_bitset(&self, id: &T) -> fixedbitset::FixedBitSet {
+        let mut neighbours = fixedbitset::FixedBitSet::with_capacity(self.segments.len());
+        if let Some(position) = self.position_of(id) {
+            let adjacency = self.adjacency();
+            for forward in [true, false].iter() {
+                for &(_, next) in adjacency.outgoing[handle(position, *forward)].iter() {
+                    neighbours.insert(next / 2);
+                }
+            }
+        }
+        neighbours
+    }
+
+    #[cfg(feature = "petgraph")]
+    /// Convert the link graph to a petgraph Graph
+    ///
+    /// Node i is segment i (weight: segment id), every link is an edge with weight (from_dir, to_dir).
+    /// Links with missing segments are skipped. Also returns the map id -> node index.
+    pub fn to_petgraph(
+        &self,
+    ) -> (
+        petgraph::Graph<T, (bool, bool)>,
+        HashMap<T, petgraph::graph::NodeIndex>,
+    )
+    where
+        T: Hash,
+    {
+        let mut graph = petgraph::Graph::with_capacity(self.segments.len(), self.links.len());
+        let mut nodes = HashMap::with_capacity(self.segments.len());
+        for segment in self.segments.iter() {
+            let node = graph.add_node(segment.id.clone());
+            nodes.insert(segment.id.clone(), node);
+        }
+        for link in self.links.iter() {
+            if let (Some(from), Some(to)) =
+                (self.position_of(&link.from), self.position_of(&link.to))
+            {
+                graph.add_edge(
+                    petgraph::graph::NodeIndex::new(from),
+                    petgraph::graph::NodeIndex::new(to),
+                    (link.from_dir, link.to_dir),
+                );
+            }
+        }
+        (graph, nodes)
+    }
+
+    /// Write the path sequences as FASTA (">name" and the path sequence)
+    ///
+    /// Paths are written in file order, lines are wrapped like in write_segments_fasta. With
+    /// trim_overlaps the overlaps between the nodes are removed (path_sequence_with_overlaps),
+    /// invalid overlaps are returned as InvalidData errors.
+    pub fn write_paths_fasta(
+        &self,
+        mut writer: impl Write,
+        width: usize,
+        trim_overlaps: bool,
+    ) -> std::io::Result<()> {
+        for path in self.paths.iter() {
+            let sequence = if trim_overlaps {
+                self.path_sequence_with_overlaps(path)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+            } else {
+                self.path_sequence(path)
+            };
+            write_fasta_record(&mut writer, &path.name, &sequence, width)?;
+        }
+        Ok(())
+    }
+
+    /// Write the segment sequences as FASTA (">id" and the sequence)
+    ///
+    /// Segments are written in id order. Sequence lines are wrapped after width bases, 0 writes
+    /// the sequence in one line.
+    pub fn write_segments_fasta(
+        &self,
+        mut writer: impl Write,
+        width: usize,
+    ) -> std::io::Result<()> {
+        for segment in self.segments.iter() {
+            write_fasta_record(
+                &mut writer,
+                &self.original_id(&segment.id),
+                segment.sequence_str(&self.sequence),
+                width,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the link graph in Graphviz DOT format (debugging aid for small graphs)
+    ///
+    /// One node per segment (id and length), one edge per link labeled with the orientations.
+    /// Links with a reverse orientation are dashed.
+    pub fn to_dot(&self, mut writer: impl Write) -> std::io::Result<()> {
+        let name = |id: &T| self.original_id(id).replace('"', "\\\"");
+        let dir = |forward: bool| if forward { '+' } else { '-' };
+        writeln!(writer, "digraph gfa {{")?;
+        writeln!(writer, "    node [shape=box];")?;
+        for segment in self.segments.iter() {
+            let id = name(&segment.id);
+            writeln!(
+                writer,
+                "    \"{}\" [label=\"{}\\n{} bp\"];",
+                id, id, segment.length
+            )?;
+        }
+        for link in self.links.iter() {
+            writeln!(
+                writer,
+                "    \"{}\" -> \"{}\" [label=\"{}{}\"{}];",
+                name(&link.from),
+                name(&link.to),
+                dir(link.from_dir),
+                dir(link.to_dir),
+                if link.from_dir && link.to_dir {
+                    ""
+                } else {
+                    ", style=dashed"
+                }
+            )?;
         }
+        writeln!(writer, "}}")
     }
 
-    /// Convert Walk to Path
-    pub fn walk_to_path(&mut self, sep: &str) {
-        for walk in self.walk.iter() {
-            let f = walk.walk_id.to_vec();
-            let o = U::parse1(None, &mut self.sequence);
-            let n = walk.sample_id.to_owned()
-                + sep
-                + &walk.hap_index.to_owned().to_string()
-                + sep
-                + &walk.seq_id
-                + ":"
-                + &walk.seq_start.to_owned().to_string()
-                + "-"
-                + &walk.seq_end.to_owned().to_string();
-            let w = walk.walk_dir.to_vec();
-            let opt = walk.opt.clone();
-            self.paths.push(Path {
-                name: n,
-                dir: w,
-                nodes: f,
-                overlap: o,
-                opt,
-            });
+    /// Sequence of a path (node sequences in path orientation)
+    ///
+    /// Overlaps are ignored, the node sequences are simply concatenated
+    pub fn path_sequence(&self, path: &Path<T, S, U>) -> String {
+        let mut sequence = String::new();
+        for (node, dir) in path.nodes.iter().zip(path.dir.iter()) {
+            sequence.push_str(&self.get_sequence_oriented(node, *dir));
         }
-        self.walk = Vec::new();
+        sequence
     }
 
-    /// Not 100%, but still okay
+    /// Node of a path at an offset of the path sequence (see path_sequence, overlaps are ignored)
     ///
-    /// Does not work with String and SeqIndex
-    pub fn is_compact(&self) -> bool {
-        self.segments[0].id == T::parse1("1", &mut String::new())
-            && self.segments[self.segments.len() - 1].id
-                == T::parse1(&self.segments.len().to_string(), &mut String::new())
+    /// Returns the node id, the offset within the node in path orientation and the orientation.
+    /// On a reverse node, the offset on the forward strand is length - 1 - offset. None if the offset
+    /// is after the end of the path or a node is not in the graph.
+    pub fn path_coord_to_node(
+        &self,
+        path: &Path<T, S, U>,
+        offset: usize,
+    ) -> Option<(T, usize, bool)> {
+        let mut start = 0;
+        for (node, dir) in path.nodes.iter().zip(path.dir.iter()) {
+            let length = self.segments[self.position_of(node)?].length as usize;
+            if offset < start + length {
+                return Some((node.clone(), offset - start, *dir));
+            }
+            start += length;
+        }
+        None
     }
 
-    /// Get a segment by id (both)
-    pub fn get_segment_by_id(&self, id: &T) -> &Segment<T, S> {
-        if self.is_digit {
-            self.get_segment_digit(id)
-        } else {
-            self.get_segment_nondigit(id)
+    /// Index of the node offsets of a path, for many path_coord_to_node lookups on the same path
+    ///
+    /// None if there is no path with this name or a node is not in the graph
+    pub fn build_path_position_index(&self, path_name: &str) -> Option<PathPositionIndex<T>> {
+        let path = self.get_path_by_name(path_name)?;
+        let mut starts = Vec::with_capacity(path.nodes.len());
+        let mut length = 0;
+        for node in path.nodes.iter() {
+            starts.push(length);
+            length += self.segments[self.position_of(node)?].length as usize;
         }
+        Some(PathPositionIndex {
+            starts,
+            nodes: path
+                .nodes
+                .iter()
+                .cloned()
+                .zip(path.dir.iter().copied())
+                .collect(),
+            length,
+        })
     }
 
-    /// Get a segment by id (digit)
-    pub fn get_segment_digit(&self, id: &T) -> &Segment<T, S> {
-        let index = self.index_of_index[id.get_usize() - self.index_low];
-        &self.segments[index]
+    /// Sequence of a path, the overlaps between consecutive nodes are removed
+    ///
+    /// Uses the overlap field of the path (comma-separated CIGARs), the bases of the next node which
+    /// are part of the overlap (M, I, S, =, X) are trimmed. "*" (or no stored overlap, e.g. U = ())
+    /// and 0M joins are blunt. Fails if the overlaps do not match the path or are longer than a node.
+    pub fn path_sequence_with_overlaps(&self, path: &Path<T, S, U>) -> Result<String, GfaError> {
+        let overlaps = path.overlap.as_str(&self.sequence);
+        if overlaps.is_empty() || overlaps == "*" {
+            return Ok(self.path_sequence(path));
+        }
+        let overlaps: Vec<&str> = overlaps.split(',').collect();
+        if overlaps.len() + 1 != path.nodes.len() {
+            return Err(GfaError::BadOverlap(format!(
+                "{} overlaps for {} nodes in path {}",
+                overlaps.len(),
+                path.nodes.len(),
+                path.name
+            )));
+        }
+        let mut sequence = String::new();
+        for (i, (node, dir)) in path.nodes.iter().zip(path.dir.iter()).enumerate() {
+            let node_sequence = self.get_sequence_oriented(node, *dir);
+            let trim = match i {
+                0 => 0,
+                _ if overlaps[i - 1] == "*" => 0,
+                _ => {
+                    cigar_lengths(overlaps[i - 1])
+                        .ok_or_else(|| GfaError::BadOverlap(overlaps[i - 1].to_string()))?
+                        .1
+                }
+            };
+            match node_sequence.get(trim..) {
+                Some(rest) => sequence.push_str(rest),
+                None => {
+                    return Err(GfaError::BadOverlap(format!(
+                        "{} is longer than node {}",
+                        overlaps[i - 1],
+                        self.original_id(node)
+                    )))
+                }
+            }
+        }
+        Ok(sequence)
     }
 
-    /// Get a segment by id (nondigit)
-    pub fn get_segment_nondigit(&self, id: &T) -> &Segment<T, S> {
-        &self.segments[self.segments.binary_search_by(|x| x.id.cmp(id)).unwrap()]
+    /// Region of the container covered by a contained segment
+    ///
+    /// Starts at pos on the forward container, the length is the reference length of the overlap CIGAR
+    /// or the length of the contained segment if there is none ("*" or U = ()). The region is reverse
+    /// complemented if the orientations differ, so it matches the contained segment as written. Cut at
+    /// the end of the container. Fails if the container or the contained segment is not in the graph.
+    pub fn containment_sequence(&self, c: &Containment<T, S>) -> Result<String, GfaError> {
+        let sequence_of = |id: &T| {
+            self.position_of(id)
+                .map(|x| self.segment_sequence(&self.segments[x]))
+                .ok_or_else(|| GfaError::BadRange(format!("no segment {}", self.original_id(id))))
+        };
+        let container = sequence_of(&c.container)?;
+        let contained = sequence_of(&c.contained)?;
+        let overlap = c.overlap.as_str(&self.sequence);
+        let length = match cigar_lengths(overlap) {
+            Some((reference, _)) if !overlap.is_empty() => reference,
+            _ => contained.len(),
+        };
+        let start = (c.pos as usize).min(container.len());
+        let region = &container[start..(start + length).min(container.len())];
+        if c.container_dir == c.contained_dir {
+            Ok(region.to_string())
+        } else {
+            Ok(reverse_complement(region))
+        }
     }
 
-    /// Get a sequence by id
-    pub fn get_sequence_by_id(&self, id: &T) -> &str {
-        self.get_segment_by_id(id)
-            .sequence
-            .get_string(&self.sequence)
+    /// Parse an oriented path string (e.g. ">5>9<3") with the ids of this graph
+    ///
+    /// Returns None if the string is malformed or a node is not in the graph. Does not work with SeqIndex ids.
+    pub fn parse_oriented_path(&self, s: &str) -> Option<(Vec<bool>, Vec<T>)> {
+        let mut scratch = String::new();
+        let (dirs, names): (Vec<bool>, Vec<SeqIndex>) = parse_walk_string(s, &mut scratch).ok()?;
+        let mut nodes = Vec::with_capacity(names.len());
+        for name in names.iter() {
+            let id = T::find_id(
+                name.get_string(&scratch),
+                &mut String::new(),
+                &self.interner,
+            )?;
+            self.position_of(&id)?;
+            nodes.push(id);
+        }
+        Some((dirs, nodes))
     }
 
-    /// Get a sequence by id (nondigit)
-    pub fn get_sequence_by_id_nondigit(&self, id: &T) -> &str {
-        self.get_segment_nondigit(id)
-            .sequence
-            .get_string(&self.sequence)
+    /// Sequence of a GAF path (e.g. ">5>9<3") from start to end (0-based, end exclusive)
+    ///
+    /// Returns None if a node is not in the graph or the range is out of bounds
+    pub fn gaf_path_sequence(&self, path: &str, start: usize, end: usize) -> Option<String> {
+        let (dirs, nodes) = self.parse_oriented_path(path)?;
+        let mut sequence = String::new();
+        for (node, dir) in nodes.iter().zip(dirs.iter()) {
+            sequence.push_str(&self.get_sequence_oriented(node, *dir));
+        }
+        sequence.get(start..end).map(|x| x.to_string())
     }
 
-    /// Get a sequence by id (digit)
-    pub fn get_sequence_by_digit(&self, id: &T) -> &str {
-        self.get_segment_digit(id)
-            .sequence
-            .get_string(&self.sequence)
+    /// Name and sequence of all paths (see path_sequence), computed in parallel
+    pub fn all_path_sequences(&self) -> Vec<(String, String)>
+    where
+        U: std::marker::Sync,
+    {
+        self.paths
+            .par_iter()
+            .map(|x| (x.name.clone(), self.path_sequence(x)))
+            .collect()
     }
 
     pub fn get_index_low(&self) -> usize {
         self.index_low
     }
 
+    /// Highest segment id (0 for an empty graph)
     pub fn get_index_high(&self) -> usize {
-        self.segments[self.segments.len() - 1].id.get_usize()
+        if self.index_of_index.is_empty() {
+            self.segments.last().map_or(0, |x| x.id.get_usize())
+        } else {
+            self.index_low + self.index_of_index.len() - 1
+        }
     }
 
     pub fn get_index_of_index(&self) -> &Vec<usize> {
@@ -665,120 +3996,471 @@ impl Gfa<u32, (), ()> {
     }
 }
 
+#[cfg(feature = "mmap")]
+/// Gfa with segment sequences in a memory-mapped file (see Gfa::parse_gfa_file_mmap)
+///
+/// The graph itself has no sequences (all segment sequences are empty), use the sequence getters
+/// of this struct. All other functions are available through graph().
+pub struct MappedGfa<
+    T: SampleType + Ord + std::marker::Send,
+    S: Opt + Ord + std::marker::Send,
+    U: Opt + std::marker::Send,
+> {
+    graph: Gfa<T, S, U>,
+    mmap: memmap2::Mmap,
+    /// Sequence of each segment in the file (same order as segments)
+    offsets: Vec<SeqIndex>,
+}
+
+#[cfg(feature = "mmap")]
+impl<
+        T: SampleType + Ord + Clone + std::marker::Send + std::marker::Sync,
+        S: Opt + Ord + Clone + std::marker::Send + std::marker::Sync,
+        U: Opt + std::marker::Send,
+    > MappedGfa<T, S, U>
+{
+    /// The parsed graph (without sequences)
+    pub fn graph(&self) -> &Gfa<T, S, U> {
+        &self.graph
+    }
+
+    /// Sequence of a segment, read from the file
+    pub fn get_segment_sequence(&self, segment: &Segment<T, S>) -> &str {
+        self.get_sequence_by_id(&segment.id)
+    }
+
+    /// Sequence of a segment by id, read from the file
+    ///
+    /// Panics if the id does not exist
+    pub fn get_sequence_by_id(&self, id: &T) -> &str {
+        let position = self
+            .graph
+            .node_index(id)
+            .expect("ERROR: SEGMENT ID NOT FOUND");
+        let [start, end] = self.offsets[position].0;
+        std::str::from_utf8(&self.mmap[start..end]).unwrap()
+    }
+
+    /// Sequence of a segment by id in the given orientation, read from the file
+    ///
+    /// The reverse strand (forward = false) is the reverse complement
+    pub fn get_sequence_oriented(&self, id: &T, forward: bool) -> Cow<'_, str> {
+        let sequence = self.get_sequence_by_id(id);
+        if forward {
+            Cow::Borrowed(sequence)
+        } else {
+            Cow::Owned(reverse_complement(sequence))
+        }
+    }
+}
+
 /// Get the version of a GFA file
-pub fn get_version(file_name: &str) -> f32 {
+///
+/// Only the header lines (and comments) at the beginning of the file are checked,
+/// the VN tag can be in any of them. Returns None if there is no version.
+pub fn get_version(file_name: &str) -> Option<f32> {
     let file = File::open(file_name).expect("ERROR: CAN NOT READ FILE\n");
     let reader = BufReader::new(file);
-    let mut version_number = 0.0;
     for line in reader.lines() {
         let l = line.unwrap();
         if l.starts_with('H') {
-            let a = l.split_whitespace().nth(1).unwrap();
-            version_number = a.split(':').nth(2).unwrap().parse().unwrap();
+            if let Some(version) = version_tag(&l) {
+                return Some(version);
+            }
+        } else if !l.is_empty() && !l.starts_with('#') {
             break;
         }
     }
-    version_number
+    None
 }
 
-/// Check if a gfa file only contains of numeric segments
-pub fn check_numeric_gfafile(file_name: &str) -> bool {
+/// Version from the VN tag of a header line
+fn version_tag(line: &str) -> Option<f32> {
+    line.split_whitespace()
+        .find_map(|x| x.strip_prefix("VN:Z:"))
+        .and_then(|x| x.parse().ok())
+}
+
+/// Version which is used if the file has no header (or no VN tag)
+pub const DEFAULT_VERSION: f32 = 1.0;
+
+/// Bytes between two progress reports (Gfa::parse_gfa_file_with_progress)
+const PROGRESS_STEP: u64 = 1 << 20;
+
+#[cfg(feature = "bincode")]
+/// First bytes of a binary snapshot (Gfa::save_binary)
+const BINARY_MAGIC: &[u8; 8] = b"GFARBIN\0";
+
+/// Summary of a GFA file (see inspect_gfa)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GfaInfo {
+    /// Version from the header (None if there is none)
+    pub version: Option<f32>,
+    /// All segment ids are numeric
+    pub numeric: bool,
+    /// Segment ids are numeric and range from 1 to the number of segments
+    pub compact: bool,
+    pub segment_count: usize,
+}
+
+/// Collect version, numeric and compact information of a gfa file in a single pass
+pub fn inspect_gfa(file_name: &str) -> GfaInfo {
     let file = File::open(file_name).expect("ERROR: CAN NOT READ FILE\n");
     let reader = BufReader::new(file);
 
+    let mut info = GfaInfo {
+        numeric: true,
+        ..Default::default()
+    };
+    let (mut min, mut max) = (u64::MAX, 0);
     for line in reader.lines() {
         let l = line.unwrap();
         if l.starts_with('S') {
-            let a = l.split_whitespace().nth(1).unwrap();
-            if a.parse::<u64>().is_err() {
-                return false;
+            info.segment_count += 1;
+            if info.numeric {
+                match l.split_whitespace().nth(1).unwrap().parse::<u64>() {
+                    Ok(id) => {
+                        min = min.min(id);
+                        max = max.max(id);
+                    }
+                    Err(_) => info.numeric = false,
+                }
             }
+        } else if l.starts_with('H') && info.version.is_none() {
+            info.version = version_tag(&l);
         }
     }
-    true
+    info.compact = info.numeric && min == 1 && max == info.segment_count as u64;
+    info
+}
+
+/// Check if a gfa file only contains of numeric segments
+pub fn check_numeric_gfafile(file_name: &str) -> bool {
+    inspect_gfa(file_name).numeric
 }
 
 /// Check if a gfa file only contains of numeric segments and is compact
 pub fn check_numeric_compact_gfafile(file_name: &str) -> (bool, bool) {
-    let file = File::open(file_name).expect("ERROR: CAN NOT READ FILE\n");
-    let reader = BufReader::new(file);
-    let mut p = Vec::new();
-    for line in reader.lines() {
-        let l = line.unwrap();
-        if l.starts_with('S') {
-            let a = l.split_whitespace().nth(1).unwrap();
-            if a.parse::<u64>().is_err() {
-                return (false, false);
-            } else {
-                p.push(a.parse::<u64>().unwrap());
-            }
-        }
-    }
-    p.sort();
-    if p[0] == 1 && p[p.len() - 1] == p.len() as u64 {
-        (true, true)
-    } else {
-        (true, false)
-    }
+    let info = inspect_gfa(file_name);
+    (info.numeric, info.compact)
+}
+
+/// Parse the steps of a P line (e.g. "1+,2-,3+")
+///
+/// Returns the orientations (true = '+') and the node ids, or an error if a step has no orientation.
+/// Ids which need the graph sequence (SeqIndex) are added to seq. InternedId get new numbers.
+pub fn parse_path_string<T: SampleType>(
+    s: &str,
+    seq: &mut String,
+) -> Result<(Vec<bool>, Vec<T>), GfaError> {
+    path_parser(s, seq, &mut Interner::default())
+}
+
+/// Parse the steps of a W line (e.g. ">1<2>3")
+///
+/// Returns the orientations (true = '>') and the node ids, or an error if the walk does not start
+/// with an orientation or an id is invalid. Ids are handled as in parse_path_string.
+pub fn parse_walk_string<T: SampleType>(
+    s: &str,
+    seq: &mut String,
+) -> Result<(Vec<bool>, Vec<T>), GfaError> {
+    walk_parser(s, seq, &mut Interner::default())
 }
 
 #[inline]
-#[allow(dead_code)]
 /// Parse a path
 ///
-/// Separate node and direction with a comma
-fn path_parser<T: SampleType>(path: &str, s: &mut String) -> (Vec<bool>, Vec<T>) {
-    let a = path.split(',');
-    let (mut dirs, mut node_id) = (
-        Vec::with_capacity(a.clone().count()),
-        Vec::with_capacity(a.clone().count()),
-    );
-    for d in a {
-        dirs.push(&d[d.len() - 1..] == "+");
-        node_id.push(SampleType::parse1(&d[..d.len() - 1], s));
+/// Separate node and direction with a comma, every node ends with '+' or '-'.
+/// An empty path ("*") has no nodes.
+fn path_parser<T: SampleType>(
+    path: &str,
+    s: &mut String,
+    interner: &mut Interner,
+) -> Result<(Vec<bool>, Vec<T>), GfaError> {
+    if path == "*" {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    // Check all steps before any id is added to the graph sequence or the interner
+    let steps = path
+        .split(',')
+        .map(parse_reference)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut node_id = Vec::with_capacity(steps.len());
+    for (name, _) in steps.iter() {
+        node_id.push(parse_id(name, s, interner)?);
     }
-    (dirs, node_id)
+    Ok((steps.iter().map(|x| x.1).collect(), node_id))
 }
 
 #[inline]
 /// Parse a walk
-fn walk_parser<T: SampleType>(walk: &str, s1: &mut String) -> (Vec<bool>, Vec<T>) {
-    let a = walk[1..].split(['<', '>']).count();
+///
+/// Every node is preceded by its orientation ('>' or '<'). An empty walk ("" or "*") has no nodes.
+/// Text before the first orientation is an error (BadDirection).
+fn walk_parser<T: SampleType>(
+    walk: &str,
+    s1: &mut String,
+    interner: &mut Interner,
+) -> Result<(Vec<bool>, Vec<T>), GfaError> {
+    if walk == "*" {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let first = walk.find(['<', '>']).unwrap_or(walk.len());
+    if first > 0 {
+        return Err(GfaError::BadDirection(walk[..first].to_owned()));
+    }
+    let a = walk.matches(['<', '>']).count();
     let (mut dirs, mut node_id) = (Vec::with_capacity(a), Vec::with_capacity(a));
-    dirs.push(walk.starts_with('>'));
-    let mut s = String::new();
-    for x in walk[1..].chars() {
+    // Start of the current node and its orientation
+    let mut current: Option<(usize, bool)> = None;
+    for (i, x) in walk.char_indices() {
         if x == '<' || x == '>' {
-            dirs.push(x == '>');
-            node_id.push(T::parse1(&s, s1));
-            s = String::new();
-        } else {
-            s.push(x);
+            if let Some((start, dir)) = current {
+                dirs.push(dir);
+                node_id.push(parse_id(&walk[start..i], s1, interner)?);
+            }
+            current = Some((i + 1, x == '>'));
+        }
+    }
+    if let Some((start, dir)) = current {
+        dirs.push(dir);
+        node_id.push(parse_id(&walk[start..], s1, interner)?);
+    }
+
+    Ok((dirs, node_id))
+}
+
+/// Split a path name created by walk_to_path
+///
+/// sample{sep}hap{sep}contig:start-end -> (sample, hap, contig, start, end)
+fn split_walk_name<'a>(name: &'a str, sep: &str) -> Option<(&'a str, u32, &'a str, i32, i32)> {
+    let mut parts = name.splitn(3, sep);
+    let sample = parts.next()?;
+    let hap = parts.next()?.parse().ok()?;
+    let (contig, range) = parts.next()?.rsplit_once(':')?;
+    let (start, end) = range.split_once('-')?;
+    Some((sample, hap, contig, start.parse().ok()?, end.parse().ok()?))
+}
+
+/// Parse an oriented segment reference (GFA2)
+///
+/// Example: "12+" -> ("12", true)
+fn parse_reference(s: &str) -> Result<(&str, bool), GfaError> {
+    match s.as_bytes().last() {
+        Some(b'+') if s.len() > 1 => Ok((&s[..s.len() - 1], true)),
+        Some(b'-') if s.len() > 1 => Ok((&s[..s.len() - 1], false)),
+        _ => Err(GfaError::BadDirection(s.to_owned())),
+    }
+}
+
+/// Parse an orientation field ("+" or "-")
+fn parse_dir(s: &str) -> Result<bool, GfaError> {
+    match s {
+        "+" => Ok(true),
+        "-" => Ok(false),
+        _ => Err(GfaError::BadDirection(s.to_owned())),
+    }
+}
+
+/// Parse a number field
+fn parse_number<N: std::str::FromStr>(s: &str) -> Result<N, GfaError> {
+    s.parse()
+        .map_err(|_| GfaError::MalformedLine(format!("invalid number '{}'", s)))
+}
+
+/// Part of a line from a field (a slice of the line) to the end, without trailing whitespace
+fn rest_of_line<'a>(line: &'a str, field: &'a str) -> &'a str {
+    line[field.as_ptr() as usize - line.as_ptr() as usize..].trim_end()
+}
+
+/// Parse an id field (see SampleType::try_parse_id)
+fn parse_id<T: SampleType>(
+    input: &str,
+    s: &mut String,
+    interner: &mut Interner,
+) -> Result<T, GfaError> {
+    T::try_parse_id(input, s, interner)
+        .ok_or_else(|| GfaError::MalformedLine(format!("invalid id '{}'", input)))
+}
+
+/// Check that a sequence only contains IUPAC nucleotide codes (upper or lower case, incl. U)
+///
+/// Returns the offset of the first other character (e.g. from a protein sequence)
+pub fn validate_sequence(sequence: &str) -> Option<usize> {
+    sequence
+        .bytes()
+        .position(|x| !b"ACGTURYSWKMBDHVN".contains(&x.to_ascii_uppercase()))
+}
+
+/// Bases of the reference and the query covered by a CIGAR string (e.g. "3M1I2M" -> (5, 6))
+///
+/// M, D, N, =, X consume the reference, M, I, S, =, X consume the query. None if it is malformed.
+fn cigar_lengths(cigar: &str) -> Option<(usize, usize)> {
+    let (mut reference, mut query) = (0, 0);
+    let mut number = 0usize;
+    let mut has_number = false;
+    for c in cigar.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            number = number.checked_mul(10)?.checked_add(digit as usize)?;
+            has_number = true;
+            continue;
+        }
+        if !has_number {
+            return None;
+        }
+        match c {
+            'M' | '=' | 'X' => {
+                reference += number;
+                query += number;
+            }
+            'D' | 'N' => reference += number,
+            'I' | 'S' => query += number,
+            'H' | 'P' => {}
+            _ => return None,
         }
+        number = 0;
+        has_number = false;
+    }
+    (!has_number).then_some((reference, query))
+}
+
+/// Write one FASTA record, the sequence is wrapped after width bases (0 = no wrapping)
+fn write_fasta_record(
+    writer: &mut impl Write,
+    name: &str,
+    sequence: &str,
+    width: usize,
+) -> std::io::Result<()> {
+    writeln!(writer, ">{}", name)?;
+    if width == 0 || sequence.is_empty() {
+        return writeln!(writer, "{}", sequence);
+    }
+    for line in sequence.as_bytes().chunks(width) {
+        writer.write_all(line)?;
+        writer.write_all(b"\n")?;
     }
-    node_id.push(T::parse1(&s, s1));
+    Ok(())
+}
+
+/// Number of G/C and of A/C/G/T bases in a sequence
+fn gc_counts(sequence: &str) -> (u64, u64) {
+    sequence.bytes().fold((0, 0), |(gc, acgt), c| match c {
+        b'G' | b'C' | b'g' | b'c' => (gc + 1, acgt + 1),
+        b'A' | b'T' | b'a' | b't' => (gc, acgt + 1),
+        _ => (gc, acgt),
+    })
+}
 
-    (dirs, node_id)
+fn gc_fraction(gc: u64, acgt: u64) -> f32 {
+    if acgt == 0 {
+        0.0
+    } else {
+        (gc as f64 / acgt as f64) as f32
+    }
 }
 
-/// Parse a string to a generic type
+/// Reverse complement of a DNA (or RNA) sequence
 ///
-/// Only needed for Jumps
-fn parse_dumb(s: &str) -> i64 {
+/// Case and IUPAC ambiguity codes are kept, unknown characters are only reversed. If the sequence
+/// contains U (RNA), A is complemented to U instead of T.
+pub fn reverse_complement(sequence: &str) -> String {
+    let rna = sequence.contains(['U', 'u']);
+    sequence
+        .chars()
+        .rev()
+        .map(|c| match c {
+            'A' if rna => 'U',
+            'a' if rna => 'u',
+            'A' => 'T',
+            'T' => 'A',
+            'C' => 'G',
+            'G' => 'C',
+            'U' => 'A',
+            'R' => 'Y',
+            'Y' => 'R',
+            'K' => 'M',
+            'M' => 'K',
+            'B' => 'V',
+            'V' => 'B',
+            'D' => 'H',
+            'H' => 'D',
+            'a' => 't',
+            't' => 'a',
+            'c' => 'g',
+            'g' => 'c',
+            'u' => 'a',
+            'r' => 'y',
+            'y' => 'r',
+            'k' => 'm',
+            'm' => 'k',
+            'b' => 'v',
+            'v' => 'b',
+            'd' => 'h',
+            'h' => 'd',
+            x => x,
+        })
+        .collect()
+}
+
+/// Parse a jump distance, "*" (unknown) is None
+fn parse_dumb(s: &str) -> Result<Option<i64>, GfaError> {
     if s == "*" {
-        -1
+        Ok(None)
     } else {
-        s.parse().unwrap()
+        parse_number(s).map(Some)
     }
 }
 
+/// Start offsets of the nodes of a path (see Gfa::build_path_position_index)
+///
+/// Translates path offsets to nodes with a binary search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPositionIndex<T> {
+    /// Start offset of each node in the path sequence
+    starts: Vec<usize>,
+    nodes: Vec<(T, bool)>,
+    length: usize,
+}
+
+impl<T: Clone> PathPositionIndex<T> {
+    /// Node at an offset of the path sequence, same result as Gfa::path_coord_to_node
+    pub fn lookup(&self, offset: usize) -> Option<(T, usize, bool)> {
+        if offset >= self.length {
+            return None;
+        }
+        // Last node which starts at or before the offset (empty nodes are skipped this way)
+        let index = self.starts.partition_point(|&x| x <= offset) - 1;
+        let (node, dir) = &self.nodes[index];
+        Some((node.clone(), offset - self.starts[index], *dir))
+    }
+
+    /// Start offset of the i-th node of the path
+    pub fn node_start(&self, i: usize) -> Option<usize> {
+        self.starts.get(i).copied()
+    }
+
+    /// Length of the path sequence
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
+/// Paths grouped under a name (genome, haplotype or path)
+pub type PathGroups<'a, T, S, U> = Vec<(String, Vec<&'a Path<T, S, U>>)>;
+
 #[derive(Debug, Clone)]
 /// PanSN-spec haplotype
 ///
-/// Merging multiple paths together
+/// Merging multiple paths (or walks) together
 pub struct Haplotype<'a, T: SampleType, S: Opt, U: Opt> {
     pub name: String,
     pub paths: Vec<&'a Path<T, S, U>>,
+    /// Only used by Pansn::from_walks
+    pub walks: Vec<&'a Walk<T, S>>,
 }
 
 #[derive(Debug, Clone)]
@@ -787,6 +4469,24 @@ pub struct Sample<'a, T: SampleType, S: Opt, U: Opt> {
     pub haplotypes: Vec<Haplotype<'a, T, S, U>>,
 }
 
+impl<'a, T: SampleType, S: Opt, U: Opt> Haplotype<'a, T, S, U> {
+    /// Sequences of all paths (in order), joined by gap
+    ///
+    /// Walks (see Pansn::from_walks) are not included
+    pub fn concat_sequence(&self, gfa: &Gfa<T, S, U>, gap: &str) -> String
+    where
+        T: Ord + Clone + std::marker::Send + std::marker::Sync,
+        S: Ord + Clone + std::marker::Send + std::marker::Sync,
+        U: std::marker::Send,
+    {
+        self.paths
+            .iter()
+            .map(|x| gfa.path_sequence(x))
+            .collect::<Vec<String>>()
+            .join(gap)
+    }
+}
+
 impl<'a, T: SampleType, S: Opt, U: Opt> Sample<'a, T, S, U> {
     pub fn get_haplo_path(&self) -> Vec<&&Path<T, S, U>> {
         let mut result = Vec::new();
@@ -805,6 +4505,8 @@ impl<'a, T: SampleType, S: Opt, U: Opt> Sample<'a, T, S, U> {
 /// [sample_name][delim][haplotype_id][delim][contig_or_scaffold_name]
 pub struct Pansn<'a, T: SampleType, S: Opt, U: Opt> {
     pub genomes: Vec<Sample<'a, T, S, U>>,
+    /// Delimiter of the path names, used to join genome and haplotype names
+    pub delimiter: String,
 }
 
 impl<'a, T: SampleType, S: Opt, U: Opt> Default for Pansn<'a, T, S, U> {
@@ -817,109 +4519,187 @@ impl<'a, T: SampleType, S: Opt, U: Opt> Pansn<'a, T, S, U> {
     pub fn new() -> Self {
         Self {
             genomes: Vec::new(),
+            delimiter: "#".to_string(),
         }
     }
 
     /// Create Pansn from a list of paths
+    ///
+    /// If any path name is not in PanSN-spec (or del is " "), each path is its own genome and haplotype
     /// ```
+    /// use gfa_reader::{Gfa, Pansn};
+    /// let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    /// let pansn = Pansn::from_graph(&graph.paths, "#");
     /// ```
     pub fn from_graph(paths: &'a [Path<T, S, U>], del: &str) -> Self {
-        let mut genomes: Vec<Sample<'a, T, S, U>> = Vec::new();
+        Self::from_graph_fields(paths, del, 3)
+    }
 
-        // All path names
-        let a: Vec<String> = paths.iter().map(|x| x.name.to_string()).collect();
+    /// Create Pansn from a list of paths with a custom number of name fields
+    ///
+    /// The first field is the genome, the second the haplotype (only if fields > 2).
+    /// With two fields (sample#contig), every genome has a single haplotype with an empty name.
+    pub fn from_graph_fields(paths: &'a [Path<T, S, U>], del: &str, fields: usize) -> Self {
+        let mut genomes: Vec<Sample<'a, T, S, U>> = Vec::new();
 
-        // Check if all path names are in Pansn-spec
-        let b = a
-            .iter()
-            .map(|x| x.split(del).collect::<Vec<&str>>().len())
-            .collect::<Vec<usize>>()
-            .iter()
-            .all(|&x| x == 3);
-
-        // If no del -> one path is one haplotype, is one genome
-        if del == " " || !b {
-            for path in paths.iter() {
-                genomes.push(Sample {
-                    name: path.name.to_string(),
-                    haplotypes: vec![Haplotype {
-                        name: path.name.to_string(),
-                        paths: vec![path],
-                    }],
-                })
-            }
+        // Genome and haplotype of each path, None if any path name is not in Pansn-spec
+        let names: Option<Vec<(&str, &str)>> = if del == " " {
+            None
         } else {
-            for path in paths.iter() {
-                let name_split: Vec<&str> = path.name.split(del).collect();
-                let genome;
-                let haplotype;
-                if name_split.len() > 1 {
-                    genome = name_split[0].to_string();
-                    haplotype = name_split[1].to_string();
-                } else {
+            paths
+                .iter()
+                .map(|x| split_pansn(&x.name, del, fields))
+                .collect()
+        };
+
+        match names {
+            // If no del -> one path is one haplotype, is one genome
+            None => {
+                for path in paths.iter() {
                     genomes.push(Sample {
                         name: path.name.to_string(),
                         haplotypes: vec![Haplotype {
                             name: path.name.to_string(),
                             paths: vec![path],
+                            walks: Vec::new(),
                         }],
-                    });
-                    panic!("No Pansn, remove sep or adjust gfa")
+                    })
                 }
-                // Gibt es schon so ein Genome?
-                if let Some((index1, _)) = genomes
-                    .iter()
-                    .enumerate()
-                    .find(|(_, item)| item.name == genome)
-                {
-                    let genome = &mut genomes[index1];
-                    // Gibt es schon ein Haplotype
-                    if let Some((index2, _)) = genome
-                        .haplotypes
-                        .iter()
-                        .enumerate()
-                        .find(|(_, item)| item.name == haplotype)
-                    {
-                        let haplo = &mut genome.haplotypes[index2];
-                        haplo.paths.push(path);
+            }
+            Some(names) => {
+                for (path, (genome, haplotype)) in paths.iter().zip(names) {
+                    // Gibt es schon so ein Genome?
+                    if let Some(genome) = genomes.iter_mut().find(|item| item.name == genome) {
+                        // Gibt es schon ein Haplotype
+                        if let Some(haplo) = genome
+                            .haplotypes
+                            .iter_mut()
+                            .find(|item| item.name == haplotype)
+                        {
+                            haplo.paths.push(path);
+                        } else {
+                            genome.haplotypes.push(Haplotype {
+                                name: haplotype.to_string(),
+                                paths: vec![path],
+                                walks: Vec::new(),
+                            });
+                        }
                     } else {
-                        let haplo = Haplotype {
-                            name: haplotype,
-                            paths: vec![path],
-                        };
-                        genome.haplotypes.push(haplo);
+                        genomes.push(Sample {
+                            name: genome.to_string(),
+                            haplotypes: vec![Haplotype {
+                                name: haplotype.to_string(),
+                                paths: vec![path],
+                                walks: Vec::new(),
+                            }],
+                        });
                     }
-                } else {
-                    let haplo = Haplotype {
-                        name: haplotype,
-                        paths: vec![path],
-                    };
-                    let genome = Sample {
-                        name: genome,
-                        haplotypes: vec![haplo],
-                    };
-                    genomes.push(genome);
                 }
             }
         }
-        Pansn { genomes }
+        Pansn {
+            genomes,
+            delimiter: del.to_string(),
+        }
+    }
+
+    /// Create Pansn from a list of walks
+    ///
+    /// Walks are grouped by sample id (genome) and haplotype index (haplotype).
+    /// The path based functions (e.g. get_haplo_path) return nothing, use the walks of the haplotypes.
+    pub fn from_walks(walks: &'a [Walk<T, S>]) -> Self {
+        let mut genomes: Vec<Sample<'a, T, S, U>> = Vec::new();
+        for walk in walks.iter() {
+            let haplotype = walk.hap_index.to_string();
+            let genome = match genomes.iter_mut().find(|x| x.name == walk.sample_id) {
+                Some(genome) => genome,
+                None => {
+                    genomes.push(Sample {
+                        name: walk.sample_id.clone(),
+                        haplotypes: Vec::new(),
+                    });
+                    genomes.last_mut().unwrap()
+                }
+            };
+            match genome.haplotypes.iter_mut().find(|x| x.name == haplotype) {
+                Some(haplo) => haplo.walks.push(walk),
+                None => genome.haplotypes.push(Haplotype {
+                    name: haplotype,
+                    paths: Vec::new(),
+                    walks: vec![walk],
+                }),
+            }
+        }
+        Pansn {
+            genomes,
+            delimiter: "#".to_string(),
+        }
     }
 
     /// Get path for each haplotype
-    pub fn get_haplo_path(&self) -> Vec<(String, Vec<&Path<T, S, U>>)> {
+    ///
+    /// Names are genome and haplotype joined by the delimiter (only the genome if the haplotype has no name)
+    pub fn get_haplo_path(&self) -> PathGroups<'_, T, S, U> {
         let mut result = Vec::new();
         for sample in self.genomes.iter() {
             for haplo in sample.haplotypes.iter() {
                 let haplo_path: Vec<_> = haplo.paths.to_vec();
-                result.push((sample.name.clone() + "#" + &haplo.name, haplo_path));
+                let name = if haplo.name.is_empty() {
+                    sample.name.clone()
+                } else {
+                    sample.name.clone() + &self.delimiter + &haplo.name
+                };
+                result.push((name, haplo_path));
             }
         }
 
         result
     }
 
+    /// Genome and haplotype name of a path
+    pub fn locate(&self, path_name: &str) -> Option<(&str, &str)> {
+        for sample in self.genomes.iter() {
+            for haplo in sample.haplotypes.iter() {
+                if haplo.paths.iter().any(|x| x.name == path_name) {
+                    return Some((&sample.name, &haplo.name));
+                }
+            }
+        }
+        None
+    }
+
+    /// Presence of each node in each genome (same order as genomes)
+    ///
+    /// Contains all segments of the graph, also the ones which are in no path.
+    /// Not useful for SeqIndex ids, since the same node has different indices in different paths.
+    pub fn node_presence(&self, gfa: &Gfa<T, S, U>) -> HashMap<T, Vec<bool>>
+    where
+        T: Ord + Clone + Hash + std::marker::Send + std::marker::Sync,
+        S: Ord + Clone + std::marker::Send + std::marker::Sync,
+        U: std::marker::Send,
+    {
+        let mut presence: HashMap<T, Vec<bool>> = gfa
+            .segments
+            .iter()
+            .map(|x| (x.id.clone(), vec![false; self.genomes.len()]))
+            .collect();
+        for (i, sample) in self.genomes.iter().enumerate() {
+            let paths = sample.haplotypes.iter().flat_map(|x| x.paths.iter());
+            let walks = sample.haplotypes.iter().flat_map(|x| x.walks.iter());
+            let nodes = paths
+                .flat_map(|x| x.nodes.iter())
+                .chain(walks.flat_map(|x| x.walk_id.iter()));
+            for node in nodes {
+                presence
+                    .entry(node.clone())
+                    .or_insert_with(|| vec![false; self.genomes.len()])[i] = true;
+            }
+        }
+        presence
+    }
+
     /// Get path for each genome
-    pub fn get_path_genome(&self) -> Vec<(String, Vec<&Path<T, S, U>>)> {
+    pub fn get_path_genome(&self) -> PathGroups<'_, T, S, U> {
         let mut result = Vec::new();
         for x in self.genomes.iter() {
             let mut aa = Vec::new();
@@ -934,7 +4714,7 @@ impl<'a, T: SampleType, S: Opt, U: Opt> Pansn<'a, T, S, U> {
     }
 
     /// Get all path
-    pub fn get_paths_direct(&self) -> Vec<(String, Vec<&Path<T, S, U>>)> {
+    pub fn get_paths_direct(&self) -> PathGroups<'_, T, S, U> {
         let mut result = Vec::new();
         for x in self.genomes.iter() {
             for y in x.haplotypes.iter() {
@@ -956,25 +4736,51 @@ impl<'a, T: SampleType, S: Opt, U: Opt> Pansn<'a, T, S, U> {
     }
 }
 
+/// Split a PanSN path name into genome and haplotype
+///
+/// [sample_name][del][haplotype_id][del][contig_or_scaffold_name], None if the name has a different number of fields.
+/// The haplotype is empty if there are less than three fields.
+fn split_pansn<'a>(name: &'a str, del: &str, fields: usize) -> Option<(&'a str, &'a str)> {
+    let split: Vec<&str> = name.split(del).collect();
+    if split.len() != fields || fields < 2 {
+        return None;
+    }
+    let haplotype = if fields > 2 { split[1] } else { "" };
+    Some((split[0], haplotype))
+}
+
 /// Index a file in equal parts
+///
+/// Returns the byte offsets of the chunk borders. Each offset is the start of a line (or the end of the file).
 pub fn index_file(file_name: &str) -> Vec<usize> {
+    index_file_with(file_name, &ChunkConfig::default())
+}
+
+/// Index a file in parts of custom size
+pub fn index_file_with(file_name: &str, config: &ChunkConfig) -> Vec<usize> {
     let mut index = vec![0];
     let path = file_name;
     let file = File::open(path).expect("ERROR: CAN NOT READ FILE\n");
-    let reader = BufReader::new(file);
+    let mut reader = BufReader::new(file);
 
     let mut total_len = 0;
     let mut chunk_size = 0;
+    let mut line = Vec::new();
 
-    for line in reader.lines() {
-        let line = line.unwrap();
-        total_len += line.len() + 1;
-        chunk_size += line.len() + 1;
-        if line.len() > 1000000 && chunk_size > 20000000 {
+    // Count the exact number of bytes (incl. line endings), so every offset is the start of a line
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line).unwrap();
+        if read == 0 {
+            break;
+        }
+        total_len += read;
+        chunk_size += read;
+        if read > config.long_line_threshold && chunk_size > config.long_line_chunk_size {
             index.push(total_len);
             chunk_size = 0;
         }
-        if chunk_size > 40000000 {
+        if chunk_size > config.chunk_size {
             index.push(total_len);
             chunk_size = 0;
         }
@@ -982,10 +4788,30 @@ pub fn index_file(file_name: &str) -> Vec<usize> {
     if chunk_size != 0 {
         index.push(total_len);
     }
+    debug!(
+        "Indexed {} bytes into {} chunks",
+        total_len,
+        index.len() - 1
+    );
 
     index
 }
 
+/// Keep the elements where mask is true
+fn retain_mask<V>(vec: &mut Vec<V>, mask: &[bool]) {
+    let mut index = 0;
+    vec.retain(|_| {
+        index += 1;
+        mask[index - 1]
+    });
+}
+
+/// Remove the trailing line ending (\n or \r\n)
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
 /// Create pairs from a vector
 ///
 /// pair -> (Value, next value)
@@ -1,4 +1,10 @@
-use gfa_reader::{check_numeric_compact_gfafile, check_numeric_gfafile, Gfa, SeqIndex};
+use gfa_reader::{
+    check_numeric_compact_gfafile, check_numeric_gfafile, get_version, index_file, index_file_with,
+    inspect_gfa, parse_path_string, parse_walk_string, reverse_complement, validate_sequence,
+    ChunkConfig, CycleError, Gfa, GfaError, InternedId, Pansn, ParseOptions, RecordType,
+    SampleType, SeqIndex, UnknownLines, ValidationError,
+};
+use std::collections::{HashMap, HashSet};
 
 #[test]
 /// Read GFA
@@ -40,7 +46,7 @@ fn read_gfa_string_vs_multi() {
 
     assert_eq!(
         gfa.segments[1].sequence.get_string(gfa.get_sequence()),
-        gfa2.segments[1].sequence.get_string(gfa.get_sequence())
+        gfa2.segments[1].sequence.get_string(gfa2.get_sequence())
     );
 
     assert_eq!(gfa.segments.len(), gfa2.segments.len());
@@ -49,7 +55,7 @@ fn read_gfa_string_vs_multi() {
 
     assert_eq!(
         gfa.segments[0].sequence.get_string(gfa.get_sequence()),
-        gfa2.segments[0].sequence.get_string(gfa.get_sequence())
+        gfa2.segments[0].sequence.get_string(gfa2.get_sequence())
     );
 
     assert_eq!(gfa.paths[0].dir, gfa2.paths[0].dir);
@@ -254,3 +260,2095 @@ fn check_numeric2() {
     let p = p.is_compact();
     assert!(p);
 }
+
+#[test]
+/// Paths through a node
+///
+/// + with and without the inverted index
+fn paths_through_node() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let names: Vec<&str> = gfa
+        .paths_through_node(&3)
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect();
+    assert_eq!(names, vec!["d#1#Chr1", "e#1#Chr1"]);
+
+    gfa.build_path_index();
+    assert_eq!(gfa.paths_through_node(&3).len(), 2);
+    assert_eq!(gfa.paths_through_node(&4).len(), 4);
+    assert_eq!(gfa.paths_through_node(&1).len(), 5);
+    assert!(gfa.paths_through_node(&100).is_empty());
+}
+
+#[test]
+/// Validate references
+///
+/// + testGraph_complex.gfa has no segment 6
+fn validate_references() {
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_compact.gfa");
+    assert!(gfa.validate_references().is_empty());
+
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert!(gfa.validate_references().is_empty());
+    gfa.segments.retain(|x| x.id != 9);
+    let errors = gfa.validate_references();
+    assert!(errors.contains(&ValidationError {
+        record: RecordType::Link,
+        index: 6,
+        id: 9
+    }));
+    assert!(errors.iter().all(|x| x.id == 9));
+    assert_eq!(
+        errors
+            .iter()
+            .filter(|x| x.record == RecordType::Path)
+            .count(),
+        5
+    );
+}
+
+#[test]
+/// Read a GFA without any segments
+fn read_gfa_empty() {
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_empty.gfa");
+    assert!(gfa.segments.is_empty());
+    assert!(!gfa.is_compact());
+    assert_eq!(gfa.get_index_high(), 0);
+    assert!(gfa.get_index_of_index().is_empty());
+    assert!(gfa.paths_through_node(&1).is_empty());
+
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file_multi("data/testGraph_empty.gfa", 2);
+    assert!(gfa.segments.is_empty());
+    assert_eq!(gfa.header.version_number, "1.0");
+    assert_eq!(
+        check_numeric_compact_gfafile("data/testGraph_empty.gfa"),
+        (true, false)
+    );
+}
+
+#[test]
+/// Read GFA2
+///
+/// + segments and edges
+fn read_gfa2_edges() {
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_2.0.gfa");
+    assert_eq!(gfa.segments.len(), 3);
+    assert_eq!(gfa.get_sequence_by_id(&1), "AAAAAAAAAA");
+    assert_eq!(gfa.get_segment_by_id(&2).length, 5);
+
+    assert_eq!(gfa.edges.len(), 2);
+    let edge = &gfa.edges[0];
+    assert_eq!(edge.id, "e1");
+    assert_eq!(
+        (edge.from, edge.from_dir, edge.to, edge.to_dir),
+        (1, true, 2, true)
+    );
+    assert_eq!(edge.from_begin.pos, 8);
+    assert!(!edge.from_begin.is_end);
+    assert!(edge.from_end.is_end);
+    assert_eq!(edge.alignment.get_string(gfa.get_sequence()), "2M");
+    assert!(!gfa.edges[1].to_dir);
+    assert!(gfa.validate_references().is_empty());
+}
+
+#[test]
+/// Read GFA2
+///
+/// + fragments and gaps
+fn read_gfa2_fragments_gaps() {
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_2.0.gfa");
+    assert_eq!(gfa.fragments.len(), 1);
+    let fragment = &gfa.fragments[0];
+    assert_eq!(fragment.segment, 2);
+    assert_eq!(fragment.external, "read1");
+    assert!(!fragment.external_dir);
+    assert!(fragment.segment_end.is_end);
+    assert_eq!(fragment.fragment_end.pos, 15);
+    assert_eq!(fragment.alignment.get_string(gfa.get_sequence()), "5M");
+
+    assert_eq!(gfa.gaps.len(), 2);
+    assert_eq!(gfa.gaps[0].distance, 100);
+    assert_eq!(gfa.gaps[0].variance, None);
+    assert_eq!(gfa.gaps[1].distance, -5);
+    assert_eq!(gfa.gaps[1].variance, Some(3));
+    assert!(!gfa.gaps[1].from_dir);
+}
+
+#[test]
+/// Unknown record types
+///
+/// + ignore, collect, error
+fn read_gfa_unknown_lines() {
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_unknown.gfa");
+    assert_eq!(gfa.segments.len(), 2);
+    assert_eq!(gfa.links.len(), 1);
+    assert!(gfa.unknown_lines.is_empty());
+
+    let options = ParseOptions {
+        unknown_lines: UnknownLines::Collect,
+        ..Default::default()
+    };
+    let gfa: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_unknown.gfa", &options).unwrap();
+    assert_eq!(gfa.unknown_lines, vec![(5, 'X'), (8, 'E')]);
+
+    let options = ParseOptions {
+        unknown_lines: UnknownLines::Error,
+        ..Default::default()
+    };
+    let gfa: Result<Gfa<u32, (), ()>, GfaError> =
+        Gfa::parse_gfa_file_with("data/testGraph_unknown.gfa", &options);
+    assert!(matches!(
+        gfa,
+        Err(GfaError::UnknownLineType {
+            line: 5,
+            record: 'X'
+        })
+    ));
+}
+
+#[test]
+/// Version of a GFA file
+///
+/// + multiple header lines, missing header
+fn read_gfa_version() {
+    assert_eq!(get_version("data/testGraph_complex.gfa"), Some(1.0));
+    assert_eq!(get_version("data/testGraph_2.0.gfa"), Some(2.0));
+    assert_eq!(get_version("data/testGraph_header.gfa"), Some(1.1));
+    assert_eq!(get_version("data/testGraph_noheader.gfa"), None);
+
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_noheader.gfa");
+    assert_eq!(gfa.get_sequence_by_id(&1), "4");
+
+    let options = ParseOptions {
+        assumed_version: Some(2.0),
+        ..Default::default()
+    };
+    let gfa: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_noheader.gfa", &options).unwrap();
+    assert_eq!(gfa.get_sequence_by_id(&1), "ACGT");
+    assert_eq!(gfa.get_segment_by_id(&2).length, 2);
+}
+
+#[test]
+/// Inspect a GFA file in a single pass
+fn inspect() {
+    let info = inspect_gfa("data/testGraph_complex.gfa");
+    assert_eq!(info.version, Some(1.0));
+    assert_eq!(info.segment_count, 8);
+    assert!(info.numeric);
+    assert!(!info.compact);
+
+    let info = inspect_gfa("data/testGraph_compact.gfa");
+    assert!(info.numeric && info.compact);
+
+    let info = inspect_gfa("data/testGraph_non-num.gfa");
+    assert!(!info.numeric && !info.compact);
+
+    let info = inspect_gfa("data/testGraph_noheader.gfa");
+    assert_eq!(info.version, None);
+    assert_eq!(info.segment_count, 2);
+}
+
+#[test]
+/// Path lookup by name
+fn path_by_name() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(
+        gfa.get_path_by_name("c#1#Chr1").unwrap().nodes,
+        vec![1, 2, 4, 9]
+    );
+    assert!(gfa.get_path_by_name("x#1#Chr1").is_none());
+
+    gfa.build_path_name_index();
+    assert_eq!(
+        gfa.get_path_by_name("c#1#Chr1").unwrap().nodes,
+        vec![1, 2, 4, 9]
+    );
+    assert_eq!(gfa.get_path_by_name("e#1#Chr2").unwrap().nodes.len(), 2);
+    assert!(gfa.get_path_by_name("x#1#Chr1").is_none());
+
+    // Indices are not compared
+    gfa.build_path_index();
+    gfa.build_adjacency();
+    let other: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(gfa, other);
+    assert_eq!(gfa.cmp(&other), std::cmp::Ordering::Equal);
+    let mut smaller = other.clone();
+    smaller.paths.pop();
+    assert!(smaller < other);
+}
+
+#[test]
+/// Walk -> Path -> Walk
+fn path_to_walk() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    let walks = gfa.walk.clone();
+    let path_count = gfa.paths.len();
+
+    gfa.walk_to_path("#");
+    assert_eq!(gfa.paths[path_count].name, "a#1#Chr1:0-100");
+    gfa.path_to_walk("#");
+    assert_eq!(gfa.walk, walks);
+    assert!(gfa.paths.is_empty());
+
+    // Names without coordinates are kept as paths
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    gfa.path_to_walk("#");
+    assert!(gfa.walk.is_empty());
+    assert_eq!(gfa.paths.len(), 6);
+
+    // Path indices are updated
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let mut path = gfa.paths[0].clone();
+    path.name = "a#1#c:0-5".to_string();
+    gfa.paths.insert(0, path);
+    gfa.build_path_name_index();
+    gfa.build_path_index();
+    gfa.path_to_walk("#");
+    assert_eq!(gfa.walk.len(), 1);
+    assert!(gfa.get_path_by_name("a#1#c:0-5").is_none());
+    for path in gfa.paths.iter() {
+        assert_eq!(gfa.get_path_by_name(&path.name), Some(path));
+    }
+    for segment in gfa.segments.iter() {
+        let scanned = gfa
+            .paths
+            .iter()
+            .filter(|x| x.nodes.contains(&segment.id))
+            .count();
+        assert_eq!(gfa.paths_through_node(&segment.id).len(), scanned);
+    }
+}
+
+#[test]
+/// Walk -> Path, keep walks
+fn walk_to_path_keep() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    gfa.walk_to_path_keep("#");
+    assert_eq!(gfa.walk.len(), 7);
+    assert_eq!(gfa.paths.len(), 7);
+    assert_eq!(gfa.paths[6].nodes, gfa.walk[6].walk_id);
+    assert_eq!(gfa.walk[0].seq_end, 100);
+
+    // Path indices include the converted walks
+    let mut gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    gfa.add_path("p", vec![1], vec![true], (), ());
+    gfa.build_path_name_index();
+    gfa.build_path_index();
+    let before = gfa.paths_through_node(&1).len();
+    gfa.walk_to_path("#");
+    assert!(gfa.get_path_by_name("a#1#Chr1:0-100").is_some());
+    assert!(gfa.paths_through_node(&1).len() > before);
+    for segment in gfa.segments.iter() {
+        let scanned = gfa
+            .paths
+            .iter()
+            .filter(|x| x.nodes.contains(&segment.id))
+            .count();
+        assert_eq!(gfa.paths_through_node(&segment.id).len(), scanned);
+    }
+}
+
+#[test]
+/// Parse walks
+///
+/// + number of directions and nodes are the same, missing orientation
+fn read_walks() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::new();
+    for walk in [">1", ">1<2", ">1>2>3", "*", "<10>200<3000"] {
+        Gfa::read_lines(format!("W\ts\t0\tc\t0\t10\t{}", walk), 1.0, &mut gfa);
+    }
+    let walks: Vec<(Vec<bool>, Vec<u32>)> = gfa
+        .walk
+        .iter()
+        .map(|x| (x.walk_dir.clone(), x.walk_id.clone()))
+        .collect();
+    assert_eq!(walks[0], (vec![true], vec![1]));
+    assert_eq!(walks[1], (vec![true, false], vec![1, 2]));
+    assert_eq!(walks[2], (vec![true, true, true], vec![1, 2, 3]));
+    assert_eq!(walks[3], (vec![], vec![]));
+    assert_eq!(walks[4], (vec![false, true, false], vec![10, 200, 3000]));
+    assert!(gfa.walk.iter().all(|x| x.walk_dir.len() == x.walk_id.len()));
+
+    // The first node has no orientation
+    let gfa: Result<Gfa<u32, (), ()>, GfaError> =
+        Gfa::parse_gfa_file_with("data/testGraph_bad_walk.gfa", &ParseOptions::default());
+    assert!(matches!(gfa, Err(GfaError::BadDirection(x)) if x == "5"));
+}
+
+#[test]
+/// Parse paths
+///
+/// + empty path, missing orientation
+fn read_paths() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::new();
+    Gfa::read_lines("P\tp1\t*\t*".to_string(), 1.0, &mut gfa);
+    Gfa::read_lines("P\tp2\t11+,2-\t*".to_string(), 1.0, &mut gfa);
+    assert!(gfa.paths[0].nodes.is_empty());
+    assert_eq!(gfa.paths[1].nodes, vec![11, 2]);
+    assert_eq!(gfa.paths[1].dir, vec![true, false]);
+
+    let gfa: Result<Gfa<u32, (), ()>, GfaError> =
+        Gfa::parse_gfa_file_with("data/testGraph_bad_path.gfa", &ParseOptions::default());
+    assert!(matches!(gfa, Err(GfaError::BadDirection(x)) if x == "2"));
+}
+
+#[test]
+/// Build a graph
+fn build_graph() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::new();
+    gfa.add_segment(2, "CCC", ());
+    gfa.add_segment(1, "AAAA", ());
+    gfa.add_link(1, true, 2, false, (), ());
+    gfa.add_path("p1", vec![1, 2], vec![true, false], (), ());
+
+    assert_eq!(gfa.segments[0].id, 1);
+    assert_eq!(gfa.get_sequence_by_id(&1), "AAAA");
+    assert_eq!(gfa.get_sequence_by_id(&2), "CCC");
+    assert_eq!(gfa.get_segment_by_id(&2).length, 3);
+    assert_eq!(gfa.links.len(), 1);
+    assert_eq!(gfa.get_path_by_name("p1").unwrap().dir, vec![true, false]);
+    assert!(gfa.validate_references().is_empty());
+    assert!(gfa.is_compact());
+
+    let mut gfa: Gfa<SeqIndex, (), ()> = Gfa::new();
+    let mut ids = String::new();
+    gfa.add_segment(<SeqIndex as SampleType>::parse1("a", &mut ids), "ACGT", ());
+    assert_eq!(gfa.get_sequence_by_id(&gfa.segments[0].id), "ACGT");
+}
+
+#[test]
+/// Store sequences in the segments
+fn read_gfa_inline_sequences() {
+    let options = ParseOptions {
+        store_sequences_inline: true,
+        ..Default::default()
+    };
+    let gfa: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_complex.gfa", &options).unwrap();
+    assert!(gfa.get_sequence().is_empty());
+    assert_eq!(gfa.get_sequence_by_id(&2), "CCCCC");
+    assert_eq!(gfa.get_sequence_oriented(&2, false), "GGGGG");
+    assert_eq!(gfa.overall_gc(), 7.0 / 27.0);
+    let segment = gfa.get_segment_by_id(&1);
+    assert_eq!(segment.owned_sequence(gfa.get_sequence()), "AAAAAAAAAA");
+    assert_eq!(gfa.segment_sequence(segment), "AAAAAAAAAA");
+
+    // A segment moved out of the graph keeps its sequence
+    let segment = gfa.get_segment_by_id(&1).clone();
+    drop(gfa);
+    assert_eq!(segment.owned_sequence(""), "AAAAAAAAAA");
+    assert_eq!(segment.length, 10);
+
+    // Inline sequences follow the segments
+    let mut gfa: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_complex.gfa", &options).unwrap();
+    let sub = gfa.subgraph(&[2, 3].iter().copied().collect());
+    assert_eq!(sub.get_sequence_by_id(&2), "CCCCC");
+    gfa.retain_segments(|x, sequence| x.id == 9 || sequence == "CCCCC");
+    assert_eq!(gfa.segments.len(), 2);
+    assert_eq!(gfa.get_sequence_by_id(&9), "AAAAAAAAAA");
+    let mut other: Gfa<u32, (), ()> = Gfa::new();
+    other.add_segment(20, "TT", ());
+    gfa.merge(other).unwrap();
+    assert_eq!(gfa.get_sequence_by_id(&2), "CCCCC");
+    assert_eq!(gfa.get_sequence_by_id(&20), "TT");
+    let (numeric, mapping) = gfa.to_numeric();
+    assert_eq!(numeric.get_sequence_by_id(&mapping["2"]), "CCCCC");
+
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let segment = gfa.get_segment_by_id(&2);
+    assert!(segment.inline_sequence.is_none());
+    assert_eq!(segment.owned_sequence(gfa.get_sequence()), "CCCCC");
+}
+
+#[test]
+/// Parse only the topology
+fn read_gfa_without_sequences() {
+    let options = ParseOptions {
+        without_sequences: true,
+        ..Default::default()
+    };
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file_with("data/size5.gfa", &options).unwrap();
+    let gfa2: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    assert!(gfa.get_sequence().is_empty());
+    assert_eq!(gfa.segments.len(), gfa2.segments.len());
+    assert_eq!(gfa.links.len(), gfa2.links.len());
+    assert_eq!(gfa.get_sequence_by_id(&6), "");
+    assert_eq!(gfa.get_segment_by_id(&6).length, 100);
+    assert_eq!(gfa2.get_sequence_by_id(&6).len(), 100);
+}
+
+#[test]
+/// Remove optional fields
+fn clear_optional() {
+    let mut gfa: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_opt.gfa");
+    assert_eq!(gfa.segments[0].opt.get_string(gfa.get_sequence()), "LN:i:4");
+    assert_eq!(gfa.links[0].opt.get_string(gfa.get_sequence()), "ID:Z:l1");
+    assert_eq!(gfa.paths[0].opt.get_len(), 9);
+
+    gfa.clear_optional();
+    assert_eq!(gfa.segments[0].opt.get_len(), 0);
+    assert_eq!(gfa.links[0].opt.get_len(), 0);
+    assert_eq!(gfa.paths[0].opt.get_len(), 0);
+    assert_eq!(gfa.get_sequence_by_id(&1), "ACGT");
+}
+
+#[test]
+/// Index over many (non-contiguous) segment ids
+fn segment_index() {
+    let mut gfa: Gfa<u32, (), ()> = Gfa::new();
+    for id in (1..3000).rev().filter(|x| x % 7 != 0) {
+        gfa.add_segment(id, "A", ());
+    }
+    for id in (1..3000).filter(|x| x % 7 != 0) {
+        assert_eq!(gfa.get_segment_by_id(&id).id, id);
+    }
+    assert_eq!(gfa.get_index_of_index().len(), 2999);
+
+    // Many segments at once, indexed only once
+    let mut gfa: Gfa<u32, (), ()> = Gfa::new();
+    gfa.add_segments((1..200_000).rev().map(|id| (id, "AC", ())));
+    assert_eq!(gfa.segments.len(), 199_999);
+    assert_eq!(gfa.get_segment_by_id(&150_000).id, 150_000);
+    assert_eq!(gfa.get_sequence_by_id(&7), "AC");
+    assert_eq!(gfa.file_order()[0], 199_998);
+}
+
+#[test]
+/// Chunk borders are line starts and cover every line exactly once
+fn index_file_lines() {
+    for file in ["data/size5.gfa", "data/testGraph_crlf.gfa"] {
+        let content = std::fs::read(file).unwrap();
+        let index = index_file(file);
+        assert_eq!(*index.last().unwrap(), content.len());
+
+        let mut lines = 0;
+        for borders in index.windows(2) {
+            assert!(borders[0] == 0 || content[borders[0] - 1] == b'\n');
+            lines += content[borders[0]..borders[1]]
+                .iter()
+                .filter(|x| **x == b'\n')
+                .count();
+        }
+        assert_eq!(lines, content.iter().filter(|x| **x == b'\n').count());
+    }
+
+    let single: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_crlf.gfa");
+    let multi: Gfa<u32, (), ()> = Gfa::parse_gfa_file_multi("data/testGraph_crlf.gfa", 2);
+    assert_eq!(single.segments.len(), multi.segments.len());
+    assert_eq!(single.paths[0].nodes, multi.paths[0].nodes);
+    assert_eq!(multi.get_sequence_by_id(&2), "CCCCC");
+}
+
+#[test]
+/// Custom chunk sizes
+fn chunk_config() {
+    let config = ChunkConfig {
+        chunk_size: 100_000,
+        ..Default::default()
+    };
+    let index = index_file_with("data/size5.gfa", &config);
+    assert!(index.len() > 10);
+    assert_eq!(index_file("data/size5.gfa").len(), 2);
+
+    let single: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let multi: Gfa<u32, (), ()> = Gfa::parse_gfa_file_multi_with("data/size5.gfa", 4, &config);
+    assert_eq!(single.segments.len(), multi.segments.len());
+    assert_eq!(single.links.len(), multi.links.len());
+    assert_eq!(single.paths.len(), multi.paths.len());
+    assert_eq!(
+        single.get_sequence_by_id(&100),
+        multi.get_sequence_by_id(&100)
+    );
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+/// Sequences from a memory-mapped file
+fn read_gfa_mmap() {
+    for file in [
+        "data/size5.gfa",
+        "data/testGraph_crlf.gfa",
+        "data/testGraph_2.0.gfa",
+        "data/testGraph_no_seq.gfa",
+    ] {
+        let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file(file);
+        let mapped: gfa_reader::MappedGfa<u32, (), ()> = Gfa::parse_gfa_file_mmap(file).unwrap();
+        assert_eq!(graph.segments.len(), mapped.graph().segments.len());
+        assert_eq!(graph.links.len(), mapped.graph().links.len());
+        for segment in graph.segments.iter() {
+            assert_eq!(
+                graph.get_sequence_by_id(&segment.id),
+                mapped.get_sequence_by_id(&segment.id)
+            );
+            assert_eq!(
+                graph.get_sequence_oriented(&segment.id, false),
+                mapped.get_sequence_oriented(&segment.id, false)
+            );
+            // The file offsets are only used by MappedGfa, the graph has no segment sequences
+            assert_eq!(mapped.graph().get_sequence_by_id(&segment.id), "");
+        }
+        for segment in mapped.graph().segments.iter() {
+            assert_eq!(
+                mapped.get_segment_sequence(segment),
+                graph.get_sequence_by_id(&segment.id)
+            );
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "requires numeric segment ids")]
+/// Digit lookups on non-numeric ids
+fn sequence_by_digit_nonnumeric() {
+    let graph: Gfa<SeqIndex, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    let id = graph.segments[0].id.clone();
+    graph.get_sequence_by_digit(&id);
+}
+
+#[test]
+/// Sequences in path orientation
+fn sequence_oriented() {
+    assert_eq!(reverse_complement("ACGTNacgtn"), "nacgtNACGT");
+    assert_eq!(reverse_complement("RYKM"), "KMRY");
+
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    let forward = graph.get_sequence_oriented(&2, true);
+    assert!(matches!(forward, std::borrow::Cow::Borrowed(_)));
+    assert_eq!(forward, graph.get_sequence_by_id(&2));
+    assert_eq!(
+        graph.get_sequence_oriented(&2, false),
+        reverse_complement(graph.get_sequence_by_id(&2))
+    );
+}
+
+#[test]
+/// Signed segment ids
+fn read_gfa_signed() {
+    let graph: Gfa<i64, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    let unsigned: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    assert_eq!(graph.segments.len(), unsigned.segments.len());
+    assert_eq!(
+        graph.get_sequence_by_id(&3),
+        unsigned.get_sequence_by_id(&3)
+    );
+
+    let graph: Gfa<i32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    assert_eq!(graph.walk[1].walk_id[3] as u32, unsigned.walk[1].walk_id[3]);
+
+    // Negative ids are an error, not a panic
+    let options = ParseOptions::default();
+    let result: Result<Gfa<i64, (), ()>, _> =
+        Gfa::parse_gfa_file_with("data/testGraph_negative.gfa", &options);
+    assert!(
+        matches!(result, Err(GfaError::MalformedLine(ref reason)) if reason == "invalid id '-5'")
+    );
+    let result: Result<Gfa<i32, (), ()>, _> =
+        Gfa::parse_gfa_file_with("data/testGraph_negative.gfa", &options);
+    assert!(result.is_err());
+}
+
+#[test]
+/// String ids interned to dense numbers
+fn read_gfa_interned() {
+    let graph: Gfa<InternedId, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    // Links and paths reference "1", which is not a segment
+    assert_eq!(graph.interner().len(), graph.segments.len() + 1);
+    assert_eq!(graph.get_index_of_index().len(), graph.segments.len());
+    for segment in graph.segments.iter() {
+        let name = graph.interner().name(&segment.id).unwrap();
+        assert_eq!(graph.interner().get(name), Some(segment.id));
+        assert_eq!(
+            graph.get_sequence_by_id(&segment.id).len(),
+            segment.length as usize
+        );
+    }
+    let nodes = &graph.paths[0].nodes;
+    assert_eq!(graph.interner().name(&nodes[0]), Some("1"));
+    assert_eq!(graph.interner().name(&nodes[1]), Some("2"));
+    assert_eq!(graph.get_sequence_by_id(&nodes[1]), "CCCCC");
+}
+
+#[test]
+/// Printable ids for every id type
+fn original_id() {
+    let file = "data/testGraph_non-num.gfa";
+    let graph: Gfa<SeqIndex, (), ()> = Gfa::parse_gfa_file(file);
+    let names: Vec<String> = graph
+        .segments
+        .iter()
+        .map(|x| graph.original_id(&x.id))
+        .collect();
+    assert!(names.contains(&"1a".to_string()));
+    assert_eq!(graph.original_id(&graph.links[0].to), "2");
+
+    let graph: Gfa<String, (), ()> = Gfa::parse_gfa_file(file);
+    assert_eq!(graph.links[0].from, "1");
+    assert_eq!(graph.original_id(&graph.segments[0].id), "1a");
+
+    let graph: Gfa<InternedId, (), ()> = Gfa::parse_gfa_file(file);
+    assert_eq!(graph.original_id(&graph.paths[0].nodes[2]), "5");
+
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    assert_eq!(graph.original_id(&graph.segments[8].id), "9");
+
+    // Every line in its own chunk, ids and tags point into the merged sequence
+    let config = ChunkConfig {
+        chunk_size: 1,
+        ..Default::default()
+    };
+    let graph: Gfa<SeqIndex, SeqIndex, ()> =
+        Gfa::parse_gfa_file_multi_with("data/testGraph_opt.gfa", 3, &config);
+    let mut names: Vec<String> = graph
+        .segments
+        .iter()
+        .map(|x| graph.original_id(&x.id))
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["1", "2"]);
+    let link = &graph.links[0];
+    assert_eq!(graph.original_id(&link.from), "1");
+    assert_eq!(graph.original_id(&link.to), "2");
+    assert_eq!(link.opt.get_string(graph.get_sequence()), "ID:Z:l1");
+    assert_eq!(graph.original_id(&graph.paths[0].nodes[1]), "2");
+}
+
+#[test]
+/// PanSN grouping, with a fallback for mixed naming
+fn pansn_from_graph() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let pansn = Pansn::from_graph(&graph.paths, "#");
+    let paths: usize = pansn.get_path_genome().iter().map(|x| x.1.len()).sum();
+    assert_eq!(paths, graph.paths.len());
+
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    graph.add_segment(1, "A", ());
+    graph.add_path("a#1#chr1", vec![1], vec![true], (), ());
+    graph.add_path("a#2#chr1", vec![1], vec![true], (), ());
+    graph.add_path("a#2#chr2", vec![1], vec![true], (), ());
+    let pansn = Pansn::from_graph(&graph.paths, "#");
+    assert_eq!(pansn.genomes.len(), 1);
+    assert_eq!(pansn.genomes[0].haplotypes.len(), 2);
+    assert_eq!(pansn.get_haplo_path()[1].1.len(), 2);
+
+    graph.add_path("b#chr1", vec![1], vec![true], (), ());
+    let pansn = Pansn::from_graph(&graph.paths, "#");
+    assert_eq!(pansn.genomes.len(), 4);
+    assert_eq!(pansn.genomes[3].name, "b#chr1");
+}
+
+#[test]
+/// PanSN with custom delimiter and two fields
+fn pansn_fields() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    graph.add_segment(1, "A", ());
+    graph.add_path("a.1.chr1", vec![1], vec![true], (), ());
+    graph.add_path("a.2.chr1", vec![1], vec![true], (), ());
+    let pansn = Pansn::from_graph(&graph.paths, ".");
+    assert_eq!(pansn.get_haplo_path()[1].0, "a.2");
+    assert_eq!(pansn.locate("a.2.chr1"), Some(("a", "2")));
+    assert_eq!(pansn.locate("a.3.chr1"), None);
+
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    graph.add_segment(1, "A", ());
+    graph.add_path("a#chr1", vec![1], vec![true], (), ());
+    graph.add_path("a#chr2", vec![1], vec![true], (), ());
+    graph.add_path("b#chr1", vec![1], vec![true], (), ());
+    let pansn = Pansn::from_graph_fields(&graph.paths, "#", 2);
+    assert_eq!(pansn.genomes.len(), 2);
+    assert_eq!(pansn.genomes[0].haplotypes.len(), 1);
+    let haplo = pansn.get_haplo_path();
+    assert_eq!(haplo[0].0, "a");
+    assert_eq!(haplo[0].1.len(), 2);
+
+    // Three fields expected, but two given
+    assert_eq!(Pansn::from_graph(&graph.paths, "#").genomes.len(), 3);
+}
+
+#[test]
+/// Node presence per genome
+fn pansn_node_presence() {
+    let graph: Gfa<InternedId, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    let pansn = Pansn::from_graph(&graph.paths, "#");
+    let presence = pansn.node_presence(&graph);
+    let id = |name: &str| graph.interner().get(name).unwrap();
+    assert_eq!(pansn.genomes.len(), 5);
+    assert_eq!(presence[&id("1")], vec![true; 5]);
+    assert_eq!(presence[&id("7")], vec![false, true, false, false, false]);
+    assert_eq!(presence[&id("3")], vec![false, false, false, true, true]);
+    assert_eq!(presence[&id("1a")], vec![false; 5]);
+    // "1" is used in paths, but is not a segment
+    assert_eq!(presence.len(), graph.segments.len() + 1);
+}
+
+#[test]
+/// Sequence of paths and haplotypes
+fn path_sequence() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    graph.add_segment(1, "AAC", ());
+    graph.add_segment(2, "GT", ());
+    graph.add_path("a#1#chr1", vec![1, 2], vec![true, false], (), ());
+    graph.add_path("a#1#chr2", vec![2], vec![true], (), ());
+    assert_eq!(graph.path_sequence(&graph.paths[0]), "AACAC");
+
+    let pansn = Pansn::from_graph(&graph.paths, "#");
+    let haplotype = &pansn.genomes[0].haplotypes[0];
+    assert_eq!(haplotype.concat_sequence(&graph, "NN"), "AACACNNGT");
+}
+
+#[test]
+/// PanSN from walks
+fn pansn_from_walks() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    let pansn: Pansn<u32, (), ()> = Pansn::from_walks(&graph.walk);
+    assert_eq!(pansn.genomes.len(), 5);
+    let e = &pansn.genomes[4];
+    assert_eq!(e.name, "e");
+    assert_eq!(e.haplotypes.len(), 1);
+    assert_eq!(e.haplotypes[0].name, "1");
+    assert_eq!(e.haplotypes[0].walks.len(), 3);
+    assert!(pansn.get_paths_direct().is_empty());
+
+    let presence = pansn.node_presence(&graph);
+    assert_eq!(presence[&7], vec![false, true, false, false, false]);
+}
+
+#[test]
+/// Induced subgraph on a set of nodes
+fn subgraph() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_1.1.gfa");
+    let sub = graph.subgraph(&HashSet::from([1, 2, 4, 9]));
+    assert_eq!(sub.segments.len(), 4);
+    assert_eq!(sub.links.len(), 3);
+    let walks: Vec<(&str, &str)> = sub
+        .walk
+        .iter()
+        .map(|x| (x.sample_id.as_str(), x.seq_id.as_str()))
+        .collect();
+    assert_eq!(walks, vec![("c", "Chr1"), ("e", "Chr2"), ("e", "Chr3")]);
+    assert_eq!(sub.get_sequence_by_id(&4), graph.get_sequence_by_id(&4));
+    assert!(sub.validate_references().is_empty());
+
+    let graph: Gfa<String, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_opt.gfa");
+    let sub = graph.subgraph(&HashSet::from(["2".to_string()]));
+    assert_eq!(sub.segments.len(), 1);
+    assert!(sub.links.is_empty() && sub.paths.is_empty());
+    assert_eq!(sub.get_sequence_by_id(&"2".to_string()), "TT");
+    assert_eq!(
+        sub.segments[0].opt.get_string(sub.get_sequence()),
+        "DP:f:2.5"
+    );
+    assert!(sub.get_sequence().len() < graph.get_sequence().len());
+}
+
+#[test]
+/// Subgraph of a single path
+fn path_subgraph() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert!(graph.path_subgraph("x#1#Chr1").is_none());
+
+    let sub = graph.path_subgraph("d#1#Chr1").unwrap();
+    let ids: Vec<u32> = sub.segments.iter().map(|x| x.id).collect();
+    assert_eq!(ids, vec![1, 3, 4, 9]);
+    let links: Vec<(u32, u32)> = sub.links.iter().map(|x| (x.from, x.to)).collect();
+    // The loop 3 -> 3 is not used by the path
+    assert_eq!(links, vec![(1, 3), (3, 4), (4, 9)]);
+    assert_eq!(sub.paths.len(), 1);
+    assert_eq!(sub.get_sequence_by_id(&3), "G");
+}
+
+#[test]
+/// Remove segments and their references
+fn retain_segments() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    // Remove all segments shorter than 2 bp (3 and 7)
+    graph.retain_segments(|_, seq| seq.len() >= 2);
+    assert_eq!(graph.segments.len(), 6);
+    assert!(graph.validate_references().is_empty());
+    assert_eq!(graph.links.len(), 6);
+    assert_eq!(graph.paths[3].nodes, vec![1, 4, 9]);
+    assert_eq!(graph.paths[3].dir.len(), 3);
+    assert_eq!(graph.get_sequence_by_id(&8), "NNNNNNNNNN");
+
+    let mut graph: Gfa<SeqIndex, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    graph.retain_segments(|x, _| x.length != 1);
+    assert_eq!(graph.links.len(), 6);
+    assert_eq!(graph.paths[4].nodes.len(), 2);
+}
+
+#[test]
+/// Merge two graphs
+fn merge() {
+    let mut graph: Gfa<String, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_opt.gfa");
+    let other: Gfa<String, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_noheader.gfa");
+    assert!(matches!(
+        graph.merge(other),
+        Err(GfaError::DuplicateSegments(ids)) if ids == vec!["1", "2"]
+    ));
+    assert_eq!(graph.segments.len(), 2);
+
+    let mut graph: Gfa<u32, SeqIndex, ()> = Gfa::new();
+    let no_opt = <SeqIndex as gfa_reader::Opt>::parse1(None, &mut String::new());
+    graph.add_segment(10, "GG", no_opt);
+    let other: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_opt.gfa");
+    graph.merge(other).unwrap();
+    assert_eq!(graph.segments.len(), 3);
+    assert_eq!(graph.header.version_number, "1.0");
+    assert_eq!(graph.get_sequence_by_id(&1), "ACGT");
+    assert_eq!(graph.get_sequence_by_id(&10), "GG");
+    assert_eq!(
+        graph.links[0].opt.get_string(graph.get_sequence()),
+        "ID:Z:l1"
+    );
+    assert!(graph.validate_references().is_empty());
+
+    let mut graph: Gfa<InternedId, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    let other: Gfa<InternedId, (), ()> = Gfa::parse_gfa_file("data/testGraph_opt.gfa");
+    assert!(matches!(graph.merge(other), Err(GfaError::InternedMerge)));
+}
+
+#[test]
+/// Duplicated links
+fn dedup_links() {
+    let file = "data/testGraph_duplicate_links.gfa";
+    let graph: Gfa<SeqIndex, SeqIndex, SeqIndex> = Gfa::parse_gfa_file(file);
+    assert_eq!(graph.count_parallel_links(false), 3);
+    assert_eq!(graph.count_parallel_links(true), 1);
+
+    let mut graph: Gfa<SeqIndex, SeqIndex, SeqIndex> = Gfa::parse_gfa_file(file);
+    assert_eq!(graph.dedup_links(true), 1);
+    assert_eq!(graph.links.len(), 4);
+    assert_eq!(graph.dedup_links(false), 2);
+    assert_eq!(graph.links.len(), 2);
+    assert!(!graph.links[1].to_dir);
+
+    // Reverse complement of a link (1+ -> 2+ is 2- -> 1-)
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    graph.add_segments(vec![(1, "A", ()), (2, "C", ())]);
+    graph.add_link(1, true, 2, true, (), ());
+    graph.add_link(2, false, 1, false, (), ());
+    graph.add_link(1, true, 2, false, (), ());
+    graph.add_link(2, true, 1, false, (), ());
+    graph.add_link(1, true, 1, false, (), ());
+    assert_eq!(graph.count_parallel_links(false), 2);
+    assert_eq!(graph.dedup_links(false), 2);
+    assert_eq!(graph.links.len(), 3);
+    assert!(graph.links[0].from_dir && graph.links[0].to_dir);
+}
+
+#[test]
+/// Link lookups (sorted and unsorted)
+fn has_link() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let links: Vec<(u32, bool, u32, bool)> = graph
+        .links
+        .iter()
+        .map(|x| (x.from, x.from_dir, x.to, x.to_dir))
+        .collect();
+    let (from, from_dir, to, to_dir) = links[100];
+    assert!(graph.has_link(&from, from_dir, &to, to_dir));
+    assert!(graph.has_link(&to, !to_dir, &from, !from_dir));
+
+    graph.sort_links();
+    for (from, from_dir, to, to_dir) in links.iter() {
+        assert!(graph.has_link(from, *from_dir, to, *to_dir));
+        assert!(graph.has_link(to, !*to_dir, from, !*from_dir));
+    }
+    assert!(!graph.has_link(&1, true, &1_000_000, true));
+
+    graph.add_link(1, true, 1_000_000, true, (), ());
+    assert!(graph.has_link(&1, true, &1_000_000, true));
+}
+
+#[test]
+/// PanSN paths to walks
+fn paths_to_walks_pansn() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    graph.add_path("ref", vec![1], vec![true], (), ());
+    graph.add_path("x#y#chr1", vec![1], vec![true], (), ());
+    let rejected = graph.paths_to_walks_pansn("#");
+    assert_eq!(rejected, vec!["ref", "x#y#chr1"]);
+    assert_eq!(graph.paths.len(), 2);
+    assert_eq!(graph.walk.len(), 6);
+
+    let walk = &graph.walk[3];
+    assert_eq!(walk.sample_id, "d");
+    assert_eq!(walk.hap_index, 1);
+    assert_eq!(walk.seq_id, "Chr1");
+    assert_eq!((walk.seq_start, walk.seq_end), (0, 31));
+    assert_eq!(walk.walk_id, vec![1, 3, 4, 9]);
+}
+
+#[test]
+/// Length without sequence
+fn total_sequence_length() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(graph.total_sequence_length(), 57);
+    assert!(graph.segments.iter().all(|x| x.has_sequence()));
+
+    let options = ParseOptions {
+        without_sequences: true,
+        ..Default::default()
+    };
+    let graph: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_complex.gfa", &options).unwrap();
+    assert_eq!(graph.total_sequence_length(), 57);
+    assert!(graph.segments.iter().all(|x| !x.has_sequence()));
+
+    // Inline sequences
+    let options = ParseOptions {
+        store_sequences_inline: true,
+        ..Default::default()
+    };
+    let graph: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_complex.gfa", &options).unwrap();
+    assert!(graph.segments.iter().all(|x| x.has_sequence()));
+    let graph: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_no_seq.gfa", &options).unwrap();
+    assert!(!graph.get_segment_by_id(&1).has_sequence());
+    assert!(graph.get_segment_by_id(&2).has_sequence());
+}
+
+#[test]
+/// Segments with "*" as sequence
+fn read_gfa_star_sequence() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_no_seq.gfa");
+    let segment = graph.get_segment_by_id(&1);
+    assert_eq!(segment.sequence.get_len(), 0);
+    assert_eq!(segment.length, 12);
+    assert!(!segment.has_sequence());
+    assert_eq!(graph.get_sequence_by_id(&1), "");
+    assert_eq!(graph.get_segment_by_id(&3).length, 0);
+    assert!(graph.get_segment_by_id(&2).has_sequence());
+    assert_eq!(graph.total_sequence_length(), 16);
+}
+
+#[test]
+/// Sequences with lower case, RNA and non-nucleotide characters
+fn validate_sequence_chars() {
+    assert_eq!(validate_sequence("ACGTUacgtuNnRYKMSWBDHV"), None);
+    assert_eq!(validate_sequence("MEEPLVK"), Some(1));
+    assert_eq!(validate_sequence("ACG T"), Some(3));
+    assert_eq!(reverse_complement("acGU"), "ACgu");
+    assert_eq!(reverse_complement("AUGC"), "GCAU");
+    assert_eq!(reverse_complement("ATGC"), "GCAT");
+}
+
+#[test]
+/// Find segments with invalid sequences
+fn validate_sequences() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_bad_seq.gfa");
+    assert_eq!(graph.validate_sequences(), vec![(3, 1), (4, 2)]);
+
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    assert!(graph.validate_sequences().is_empty());
+}
+
+#[test]
+/// Sequences of all paths
+fn all_path_sequences() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let sequences = graph.all_path_sequences();
+    assert_eq!(sequences.len(), graph.paths.len());
+    for (path, (name, sequence)) in graph.paths.iter().zip(sequences.iter()) {
+        assert_eq!(&path.name, name);
+        assert_eq!(&graph.path_sequence(path), sequence);
+    }
+}
+
+#[test]
+/// GAF path strings
+fn gaf_path() {
+    let mut seq = String::new();
+    let (dirs, nodes): (Vec<bool>, Vec<u32>) = parse_walk_string(">5>9<3", &mut seq).unwrap();
+    assert_eq!(dirs, vec![true, true, false]);
+    assert_eq!(nodes, vec![5, 9, 3]);
+
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    // CCCCC + C (reverse of 3: G) + NNN
+    assert_eq!(
+        graph.gaf_path_sequence(">2<3>4", 3, 9),
+        Some("CCCNNN".to_string())
+    );
+    assert_eq!(graph.gaf_path_sequence(">2<6", 0, 1), None);
+    assert_eq!(graph.gaf_path_sequence(">2", 0, 10), None);
+
+    let graph: Gfa<InternedId, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    let (_, nodes) = graph.parse_oriented_path(">1a<2").unwrap();
+    assert_eq!(graph.original_id(&nodes[1]), "2");
+    assert!(graph.parse_oriented_path(">x").is_none());
+    assert!(graph.parse_oriented_path("1a>2").is_none());
+}
+
+#[test]
+/// Public path and walk string parsers
+fn parse_path_walk_string() {
+    let mut seq = String::new();
+    let (dirs, nodes): (Vec<bool>, Vec<u64>) = parse_path_string("1+,20-,3+", &mut seq).unwrap();
+    assert_eq!(dirs, vec![true, false, true]);
+    assert_eq!(nodes, vec![1, 20, 3]);
+    assert!(parse_path_string::<u64>("1+,2", &mut seq).is_err());
+    assert!(parse_path_string::<u64>("*", &mut seq)
+        .unwrap()
+        .1
+        .is_empty());
+
+    let (dirs, nodes): (Vec<bool>, Vec<SeqIndex>) = parse_walk_string("<a>bb", &mut seq).unwrap();
+    assert_eq!(dirs, vec![false, true]);
+    assert_eq!(nodes[1].get_string(&seq), "bb");
+    assert!(matches!(
+        parse_walk_string::<u64>("5>9", &mut seq),
+        Err(GfaError::BadDirection(x)) if x == "5"
+    ));
+    assert!(matches!(
+        parse_walk_string::<u64>(">1<x", &mut seq),
+        Err(GfaError::MalformedLine(_))
+    ));
+    assert!(parse_walk_string::<u64>("*", &mut seq)
+        .unwrap()
+        .1
+        .is_empty());
+}
+
+#[test]
+/// Topological order and cycle detection
+fn topological_sort() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    // Self loop 3+ -> 3+ (or 3- -> 3-)
+    let error: CycleError<u32> = graph.topological_sort().unwrap_err();
+    assert_eq!(error.cycle.len(), 1);
+    assert_eq!(error.cycle[0].0, 3);
+
+    graph.links.retain(|x| x.from != x.to);
+    graph.build_adjacency();
+    let order = graph.topological_sort().unwrap();
+    assert_eq!(order.len(), graph.segments.len());
+    let rank = |id: &u32| order.iter().position(|x| x == id).unwrap();
+    for link in graph.links.iter() {
+        assert!(rank(&link.from) < rank(&link.to));
+    }
+
+    graph.add_link(9, true, 1, true, (), ());
+    let cycle = graph.topological_sort().unwrap_err().cycle;
+    assert!(cycle.len() >= 3);
+    assert!(graph.has_link(
+        &cycle[cycle.len() - 1].0,
+        cycle[cycle.len() - 1].1,
+        &cycle[0].0,
+        cycle[0].1
+    ));
+}
+
+#[test]
+/// DAG check
+fn is_acyclic() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert!(!graph.is_acyclic());
+    graph.links.retain(|x| x.from != x.to);
+    assert!(graph.is_acyclic());
+
+    // Second component with a cycle through both orientations
+    graph.add_segment(20, "A", ());
+    graph.add_segment(21, "C", ());
+    graph.add_link(20, true, 21, false, (), ());
+    graph.add_link(21, false, 20, true, (), ());
+    graph.build_adjacency();
+    assert!(!graph.is_acyclic());
+}
+
+#[test]
+/// Segment length histogram, N50 and L50
+fn length_histogram() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let histogram = graph.length_histogram(5);
+    assert_eq!(
+        histogram.into_iter().collect::<Vec<_>>(),
+        vec![(0, 2), (5, 1), (10, 5)]
+    );
+    assert_eq!(graph.length_histogram(100).get(&0), Some(&8));
+
+    // Lengths 10, 10, 10, 10, 10, 5, 1, 1 (total 57)
+    assert_eq!(graph.n50(), 10);
+    assert_eq!(graph.l50(), 3);
+
+    let graph: Gfa<u32, (), ()> = Gfa::new();
+    assert_eq!(graph.n50_l50(), (0, 0));
+    assert!(graph.length_histogram(10).is_empty());
+}
+
+#[test]
+/// Bidirected neighbours of a node
+fn traverse_from() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(graph.traverse_from(&2, true), vec![(4, true), (5, true)]);
+    assert_eq!(graph.traverse_from(&4, false), vec![(2, false), (3, false)]);
+    assert_eq!(graph.traverse_from(&3, true), vec![(4, true), (3, true)]);
+    assert_eq!(graph.traverse_from(&3, false), vec![(1, false), (3, false)]);
+    assert!(graph.traverse_from(&6, true).is_empty());
+
+    // L 1 - 2 + is also 2- -> 1+
+    graph.add_link(1, false, 2, true, (), ());
+    graph.build_adjacency();
+    assert_eq!(graph.traverse_from(&2, false), vec![(1, false), (1, true)]);
+}
+
+#[test]
+/// Source and sink nodes
+fn tips() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(graph.tips(), (vec![1], vec![9]));
+
+    // 1- -> 10+ connects the starts of 1 and 10 (the end of 10 is free), 11 is isolated
+    graph.add_segment(10, "A", ());
+    graph.add_segment(11, "A", ());
+    graph.add_link(1, false, 10, true, (), ());
+    assert_eq!(graph.tips(), (vec![11], vec![9, 10, 11]));
+}
+
+#[test]
+/// Self links
+fn self_loops() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let loops = graph.self_loops();
+    assert_eq!(loops.len(), 1);
+    assert_eq!((loops[0].from, loops[0].to), (3, 3));
+
+    graph.add_link(5, true, 5, false, (), ());
+    assert_eq!(graph.self_loops().len(), 2);
+}
+
+#[test]
+/// Links starting or ending at a node, with and without adjacency index
+fn links_from_to() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    graph.add_link(5, false, 3, false, (), ());
+    for _ in 0..2 {
+        let from: Vec<(u32, u32)> = graph.links_from(&3).map(|x| (x.from, x.to)).collect();
+        assert_eq!(from, vec![(3, 4), (3, 3)]);
+        let mut to: Vec<(u32, u32)> = graph.links_to(&3).map(|x| (x.from, x.to)).collect();
+        to.sort();
+        assert_eq!(to, vec![(1, 3), (3, 3), (5, 3)]);
+        assert_eq!(graph.links_to(&1).count(), 0);
+        graph.build_adjacency();
+    }
+}
+
+#[test]
+#[cfg(feature = "serde")]
+/// Serialize and deserialize a graph (including the index)
+fn serde_roundtrip() {
+    let graph: Gfa<u32, SeqIndex, SeqIndex> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let json = serde_json::to_string(&graph).unwrap();
+    let graph2: Gfa<u32, SeqIndex, SeqIndex> = serde_json::from_str(&json).unwrap();
+    assert_eq!(graph, graph2);
+    assert_eq!(graph2.get_sequence_by_id(&5), "NNNNNNNNNN");
+    assert_eq!(graph2.get_index_low(), graph.get_index_low());
+}
+
+#[test]
+#[cfg(feature = "bincode")]
+/// Save and load a binary snapshot
+fn binary_snapshot() {
+    let graph: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let file = std::env::temp_dir().join("gfa_reader_snapshot.bin");
+    let file = file.to_str().unwrap();
+    graph.save_binary(file).unwrap();
+    let graph2: Gfa<u32, SeqIndex, ()> = Gfa::load_binary(file).unwrap();
+    assert_eq!(graph, graph2);
+    assert_eq!(graph2.get_sequence_by_id(&2), "CCCCC");
+
+    assert!(matches!(
+        Gfa::<u32, SeqIndex, ()>::load_binary("data/testGraph_complex.gfa"),
+        Err(GfaError::BadSnapshot(_))
+    ));
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+/// Graphviz output
+fn to_dot() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    graph.add_link(9, false, 1, true, (), ());
+    let mut dot = Vec::new();
+    graph.to_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph gfa {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("    \"2\" [label=\"2\\n5 bp\"];\n"));
+    assert!(dot.contains("    \"1\" -> \"2\" [label=\"++\"];\n"));
+    assert!(dot.contains("    \"9\" -> \"1\" [label=\"-+\", style=dashed];\n"));
+    assert_eq!(dot.matches(" -> ").count(), graph.links.len());
+}
+
+#[test]
+#[cfg(feature = "petgraph")]
+/// Conversion to petgraph
+fn to_petgraph() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    let (pgraph, nodes) = graph.to_petgraph();
+    assert_eq!(pgraph.node_count(), 8);
+    assert_eq!(pgraph.edge_count(), 11);
+    assert_eq!(pgraph[nodes[&7]], 7);
+    let edge = pgraph.find_edge(nodes[&5], nodes[&8]).unwrap();
+    assert_eq!(pgraph[edge], (true, true));
+    assert!(pgraph.find_edge(nodes[&8], nodes[&5]).is_none());
+}
+
+#[test]
+/// Merge non-branching chains into unitigs
+fn compact_unitigs() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    for (id, seq) in [
+        (1, "AC"),
+        (2, "G"),
+        (3, "TT"),
+        (4, "A"),
+        (5, "C"),
+        (6, "GG"),
+    ]
+    .iter()
+    {
+        graph.add_segment(*id, seq, ());
+    }
+    graph.add_link(1, true, 2, false, (), ());
+    graph.add_link(2, false, 3, true, (), ());
+    graph.add_link(3, true, 4, true, (), ());
+    graph.add_link(3, true, 5, true, (), ());
+    graph.add_link(5, true, 6, true, (), ());
+
+    let (compact, mapping) = graph.compact_unitigs();
+    assert_eq!(compact.segments.len(), 3);
+    assert_eq!(compact.get_sequence_by_id(&1), "ACCTT");
+    assert_eq!(compact.get_segment_by_id(&1).length, 5);
+    assert_eq!(compact.get_sequence_by_id(&5), "CGG");
+    assert_eq!(compact.links.len(), 2);
+    assert!(compact.has_link(&1, true, &4, true));
+    assert!(compact.has_link(&1, true, &5, true));
+
+    assert_eq!(mapping[&2], (1, 2, false));
+    assert_eq!(mapping[&3], (1, 3, true));
+    assert_eq!(mapping[&4], (4, 0, true));
+    assert_eq!(mapping[&6], (5, 1, true));
+}
+
+#[test]
+/// Parse multiple files into one graph
+fn parse_gfa_files() {
+    let graph: Gfa<u32, SeqIndex, ()> =
+        Gfa::parse_gfa_files(&["data/testGraph_complex.gfa"]).unwrap();
+    assert_eq!(graph.segments.len(), 8);
+
+    let result: Result<Gfa<u32, SeqIndex, ()>, GfaError> =
+        Gfa::parse_gfa_files(&["data/testGraph_complex.gfa", "data/testGraph_complex.gfa"]);
+    assert!(matches!(result, Err(GfaError::DuplicateSegments(ids)) if ids.len() == 8));
+
+    let result: Result<Gfa<u32, SeqIndex, ()>, GfaError> =
+        Gfa::parse_gfa_files(&["data/testGraph_complex.gfa", "data/does_not_exist.gfa"]);
+    assert!(matches!(result, Err(GfaError::Io(_))));
+
+    // Interned ids of different files (or chunks) are independent
+    let result: Result<Gfa<InternedId, (), ()>, GfaError> =
+        Gfa::parse_gfa_files(&["data/testGraph_non-num.gfa", "data/testGraph_opt.gfa"]);
+    assert!(matches!(result, Err(GfaError::InternedMerge)));
+    let config = ChunkConfig {
+        chunk_size: 1,
+        ..Default::default()
+    };
+    let result: Result<Gfa<InternedId, (), ()>, GfaError> =
+        Gfa::try_parse_gfa_file_multi_with("data/testGraph_non-num.gfa", 3, &config);
+    assert!(matches!(result, Err(GfaError::InternedMerge)));
+    let result: Result<Gfa<u32, (), ()>, GfaError> =
+        Gfa::try_parse_gfa_file_multi_with("data/testGraph_malformed.gfa", 3, &config);
+    assert!(matches!(result, Err(GfaError::MalformedLine(_))));
+}
+
+#[test]
+/// Progress callback while parsing
+fn parse_progress() {
+    let mut reports = Vec::new();
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file_with_progress(
+        "data/testGraph_complex.gfa",
+        &ParseOptions::default(),
+        |x| reports.push(x),
+    )
+    .unwrap();
+    assert_eq!(graph.segments.len(), 8);
+    let size = std::fs::metadata("data/testGraph_complex.gfa")
+        .unwrap()
+        .len();
+    assert_eq!(reports, vec![size]);
+}
+
+#[test]
+/// Segments in file order
+fn file_order() {
+    let graph: Gfa<String, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let order = graph.file_order();
+    assert_eq!(order.len(), graph.segments.len());
+    let ids: Vec<&str> = order
+        .iter()
+        .take(12)
+        .map(|&x| graph.segments[x].id.as_str())
+        .collect();
+    assert_eq!(
+        ids,
+        vec!["1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12"]
+    );
+
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    for id in [5, 2, 9, 1].iter() {
+        graph.add_segment(*id, "A", ());
+    }
+    let ids = |graph: &Gfa<u32, (), ()>| -> Vec<u32> {
+        graph
+            .file_order()
+            .iter()
+            .map(|&x| graph.segments[x].id)
+            .collect()
+    };
+    assert_eq!(ids(&graph), vec![5, 2, 9, 1]);
+    graph.retain_segments(|x, _| x.id != 2);
+    assert_eq!(ids(&graph), vec![5, 9, 1]);
+}
+
+#[test]
+/// All header tags are kept and written back
+fn header_tags() {
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_header.gfa");
+    assert_eq!(gfa.header.version_number, "1.1");
+    assert_eq!(
+        gfa.header.tags,
+        vec![
+            ("CL".to_string(), 'Z', "tool".to_string()),
+            ("VN".to_string(), 'Z', "1.1".to_string())
+        ]
+    );
+    assert_eq!(gfa.header.to_string(), "H\tCL:Z:tool\tVN:Z:1.1");
+
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(gfa.header.to_string(), "H\tVN:Z:1.0\tCL:Z:");
+    assert_eq!(Gfa::<u32, (), ()>::new().header.to_string(), "H");
+}
+
+#[test]
+/// Tab-only field splitting keeps spaces in tags
+fn strict_tabs() {
+    let options = ParseOptions {
+        strict_tabs: true,
+        ..Default::default()
+    };
+    let gfa: Gfa<u32, SeqIndex, ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_spaces.gfa", &options).unwrap();
+    let opt = gfa.get_segment_by_id(&1).opt.get_string(gfa.get_sequence());
+    assert_eq!(opt, "CO:Z:first segment");
+    assert_eq!(
+        gfa.header.tags[1],
+        ("CO".to_string(), 'Z', "two words".to_string())
+    );
+    assert_eq!(gfa.links.len(), 1);
+
+    // Optional fields of segments are stored as in the line, header tags are split on spaces
+    let gfa: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_spaces.gfa");
+    let opt = gfa.get_segment_by_id(&1).opt.get_string(gfa.get_sequence());
+    assert_eq!(opt, "CO:Z:first segment");
+    assert_eq!(gfa.header.tags[1].2, "two");
+}
+
+#[test]
+/// CRLF files with many small chunks give the same graph as the single-threaded parser
+fn multi_crlf_small_chunks() {
+    let config = ChunkConfig {
+        chunk_size: 32,
+        ..Default::default()
+    };
+    assert!(index_file_with("data/testGraph_crlf.gfa", &config).len() > 5);
+    let single: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_crlf.gfa");
+    let multi: Gfa<u32, SeqIndex, ()> =
+        Gfa::parse_gfa_file_multi_with("data/testGraph_crlf.gfa", 3, &config);
+    assert_eq!(single.segments.len(), multi.segments.len());
+    assert_eq!(single.links.len(), multi.links.len());
+    assert_eq!(single.paths.len(), multi.paths.len());
+    for segment in single.segments.iter() {
+        assert_eq!(
+            single.get_sequence_by_id(&segment.id),
+            multi.get_sequence_by_id(&segment.id)
+        );
+    }
+}
+
+#[test]
+/// Count lines per record type without parsing
+fn census() {
+    let counts = Gfa::<u32, (), ()>::census("data/testGraph_complex.gfa").unwrap();
+    assert_eq!(counts.len(), 4);
+    assert_eq!(counts[&'S'], 8);
+    assert_eq!(counts[&'L'], 11);
+    assert_eq!(counts[&'P'], 6);
+    assert_eq!(counts[&'H'], 1);
+
+    let counts = Gfa::<u32, (), ()>::census("data/testGraph_unknown.gfa").unwrap();
+    assert_eq!(counts[&'X'], 1);
+    assert!(Gfa::<u32, (), ()>::census("data/does_not_exist.gfa").is_err());
+}
+
+#[test]
+/// Preallocated graphs
+fn with_capacity() {
+    let graph: Gfa<u32, (), ()> = Gfa::with_capacity(10, 20, 3, 100);
+    assert!(graph.segments.capacity() >= 10);
+    assert!(graph.links.capacity() >= 20);
+    assert!(graph.paths.capacity() >= 3);
+    assert!(graph.segments.is_empty());
+
+    let options = ParseOptions {
+        preallocate: true,
+        ..Default::default()
+    };
+    let graph: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with("data/testGraph_complex.gfa", &options).unwrap();
+    assert_eq!(graph, Gfa::parse_gfa_file("data/testGraph_complex.gfa"));
+}
+
+#[test]
+/// Without shuffling, the multithreaded parser gives exactly the single-threaded graph
+fn multi_deterministic() {
+    let config = ChunkConfig {
+        chunk_size: 10_000,
+        ..Default::default()
+    };
+    let single: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let multi: Gfa<u32, SeqIndex, ()> =
+        Gfa::parse_gfa_file_multi_with("data/size5.gfa", 4, &config);
+    assert_eq!(single.segments, multi.segments);
+    assert_eq!(single.links, multi.links);
+    assert_eq!(single.paths, multi.paths);
+    assert_eq!(single.get_sequence(), multi.get_sequence());
+    assert_eq!(single.file_order(), multi.file_order());
+    assert_eq!(single, multi);
+}
+
+#[test]
+/// Default and zero thread counts
+fn multi_thread_count() {
+    let single: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let auto: Gfa<u32, (), ()> = Gfa::parse_gfa_file_multi_auto("data/size5.gfa");
+    let zero: Gfa<u32, (), ()> = Gfa::parse_gfa_file_multi("data/size5.gfa", 0);
+    assert_eq!(single, auto);
+    assert_eq!(single, zero);
+}
+
+#[test]
+/// Files smaller than one chunk (and empty files) in the multithreaded parser
+fn multi_single_chunk() {
+    let single: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_tiny.gfa");
+    let multi: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file_multi("data/testGraph_tiny.gfa", 4);
+    assert_eq!(single, multi);
+    assert_eq!(multi.get_sequence_by_id(&2), "TT");
+
+    let file = std::env::temp_dir().join("gfa_reader_empty.gfa");
+    std::fs::write(&file, "").unwrap();
+    let multi: Gfa<u32, (), ()> = Gfa::parse_gfa_file_multi(file.to_str().unwrap(), 4);
+    assert!(multi.segments.is_empty());
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+/// Node index and id are inverse
+fn id_at() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    for i in 0..graph.len() {
+        assert_eq!(graph.node_index(graph.id_at(i)), Some(i));
+    }
+    assert_eq!(graph.id_at(0), &1);
+    assert_eq!(graph.node_index(&6), None);
+
+    let graph: Gfa<String, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    assert_eq!(graph.id_at(0), "1a");
+    for i in 0..graph.len() {
+        assert_eq!(graph.node_index(graph.id_at(i)), Some(i));
+    }
+}
+
+#[test]
+/// Dense node positions
+fn node_index() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(graph.len(), 8);
+    assert!(!graph.is_empty());
+    assert_eq!(graph.node_index(&1), Some(0));
+    assert_eq!(graph.node_index(&7), Some(5));
+    assert_eq!(graph.node_index(&6), None);
+    assert_eq!(graph.node_index(&100), None);
+    for (i, segment) in graph.segments.iter().enumerate() {
+        assert_eq!(graph.node_index(&segment.id), Some(i));
+    }
+
+    let graph: Gfa<String, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    assert_eq!(graph.node_index(&"1a".to_string()), Some(0));
+    assert!(Gfa::<u32, (), ()>::new().is_empty());
+}
+
+#[test]
+/// Path sequences with trimmed overlaps
+fn path_sequence_with_overlaps() {
+    let graph: Gfa<u32, (), SeqIndex> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    let sequence =
+        |name: &str| graph.path_sequence_with_overlaps(graph.get_path_by_name(name).unwrap());
+    assert_eq!(sequence("p1").unwrap(), "ACGTACGGTT");
+    assert_eq!(sequence("p2").unwrap(), "ACGTACACGG");
+    assert!(matches!(sequence("p3"), Err(GfaError::BadOverlap(_))));
+    assert_eq!(sequence("p4").unwrap(), "GGTTCCGT");
+    assert!(matches!(sequence("p5"), Err(GfaError::BadOverlap(_))));
+
+    // Overlaps are not stored
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    let path = graph.get_path_by_name("p1").unwrap();
+    assert_eq!(
+        graph.path_sequence_with_overlaps(path).unwrap(),
+        graph.path_sequence(path)
+    );
+}
+
+#[test]
+/// Container regions of C lines
+fn containment_sequence() {
+    let graph: Gfa<u32, (), SeqIndex> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    assert_eq!(graph.containment.len(), 2);
+    // No CIGAR: length of the contained segment, other orientation
+    assert_eq!(
+        graph.containment_sequence(&graph.containment[0]).unwrap(),
+        "TACG"
+    );
+    // CIGAR reference length
+    assert_eq!(
+        graph.containment_sequence(&graph.containment[1]).unwrap(),
+        "ACG"
+    );
+
+    // Dangling containment (see validate_references)
+    let mut containment = graph.containment[1].clone();
+    containment.contained = 99;
+    assert!(matches!(
+        graph.containment_sequence(&containment),
+        Err(GfaError::BadRange(x)) if x == "no segment 99"
+    ));
+}
+
+#[test]
+/// Jump distances, "*" is None
+fn jump_distance() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_jumps.gfa");
+    let distances: Vec<Option<i64>> = graph.jump.iter().map(|x| x.distance).collect();
+    assert_eq!(distances, vec![Some(100), None, Some(-5)]);
+}
+
+#[test]
+/// Jump neighbours with and without the jump index
+fn jump_neighbors() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_jumps.gfa");
+    for indexed in [false, true] {
+        if indexed {
+            graph.build_adjacency_with_jumps();
+        }
+        assert_eq!(graph.jump_neighbors(&1), vec![(3, None), (4, Some(-5))]);
+        assert_eq!(graph.jump_neighbors(&2), vec![(3, Some(100))]);
+        assert_eq!(graph.jump_neighbors(&3), vec![(2, Some(100)), (1, None)]);
+        assert!(graph.jump_neighbors(&5).is_empty());
+    }
+    // Jumps are not links
+    assert_eq!(graph.tips(), (vec![1, 3, 4], vec![2, 3, 4]));
+}
+
+#[test]
+/// GC content per segment and of the graph
+fn gc_content() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    assert_eq!(graph.segments[0].gc_content(graph.get_sequence()), 0.5);
+    assert_eq!(graph.segments[1].gc_content(graph.get_sequence()), 0.75);
+    assert_eq!(graph.overall_gc(), 8.0 / 14.0);
+
+    // Ambiguity codes are ignored, lower case is counted
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    graph.add_segment(1, "NNgcRa", ());
+    graph.add_segment(2, "NNN", ());
+    assert_eq!(
+        graph.segments[0].gc_content(graph.get_sequence()),
+        2.0 / 3.0
+    );
+    assert_eq!(graph.segments[1].gc_content(graph.get_sequence()), 0.0);
+    assert_eq!(graph.overall_gc(), 2.0 / 3.0);
+}
+
+#[test]
+/// K-mer counts, canonical and as written
+fn count_kmers() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    let counts = |x: HashMap<Vec<u8>, u64>| {
+        let mut counts: Vec<(String, u64)> = x
+            .into_iter()
+            .map(|(kmer, count)| (String::from_utf8(kmer).unwrap(), count))
+            .collect();
+        counts.sort();
+        counts
+    };
+    let expected = |x: &[(&str, u64)]| -> Vec<(String, u64)> {
+        x.iter()
+            .map(|(kmer, count)| (kmer.to_string(), *count))
+            .collect()
+    };
+    assert_eq!(
+        counts(graph.count_kmers(3)),
+        expected(&[("AAC", 1), ("ACC", 1), ("ACG", 3), ("CCG", 1), ("GTA", 2)])
+    );
+    assert_eq!(
+        counts(graph.count_kmers_with(3, false)),
+        expected(&[
+            ("ACG", 2),
+            ("CGG", 1),
+            ("CGT", 1),
+            ("GGT", 1),
+            ("GTA", 1),
+            ("GTT", 1),
+            ("TAC", 1)
+        ])
+    );
+    assert!(graph.count_kmers(7).is_empty());
+
+    // K-mers with N are skipped, lower case is counted
+    let mut graph: Gfa<u32, (), ()> = Gfa::new();
+    graph.add_segment(1, "gtNAC", ());
+    assert_eq!(counts(graph.count_kmers(2)), expected(&[("AC", 2)]));
+}
+
+#[test]
+/// Segments as FASTA
+fn write_segments_fasta() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    let mut fasta = Vec::new();
+    graph.write_segments_fasta(&mut fasta, 0).unwrap();
+    assert_eq!(
+        String::from_utf8(fasta).unwrap(),
+        ">1\nACGTAC\n>2\nACGG\n>3\nGGTT\n"
+    );
+    let mut fasta = Vec::new();
+    graph.write_segments_fasta(&mut fasta, 4).unwrap();
+    assert_eq!(
+        String::from_utf8(fasta).unwrap(),
+        ">1\nACGT\nAC\n>2\nACGG\n>3\nGGTT\n"
+    );
+}
+
+#[test]
+/// Paths as FASTA, with and without overlaps
+fn write_paths_fasta() {
+    let mut graph: Gfa<u32, (), SeqIndex> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    graph.paths.retain(|x| x.name == "p1" || x.name == "p4");
+    let mut fasta = Vec::new();
+    graph.write_paths_fasta(&mut fasta, 0, false).unwrap();
+    assert_eq!(
+        String::from_utf8(fasta).unwrap(),
+        ">p1\nACGTACACGGGGTT\n>p4\nGGTTCCGT\n"
+    );
+    let mut fasta = Vec::new();
+    graph.write_paths_fasta(&mut fasta, 5, true).unwrap();
+    assert_eq!(
+        String::from_utf8(fasta).unwrap(),
+        ">p1\nACGTA\nCGGTT\n>p4\nGGTTC\nCGT\n"
+    );
+
+    // p3 has too many overlaps
+    let graph: Gfa<u32, (), SeqIndex> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    let error = graph.write_paths_fasta(Vec::new(), 0, true).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+/// Rename paths, the name index follows
+fn rename_paths() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    graph.build_path_name_index();
+    graph.rename_paths(|x| format!("sample#1#{}", x));
+    assert_eq!(graph.paths[0].name, "sample#1#p1");
+    assert!(graph.get_path_by_name("p1").is_none());
+    assert_eq!(
+        graph.get_path_by_name("sample#1#p4").unwrap().nodes,
+        vec![3, 2]
+    );
+}
+
+#[test]
+/// Only parse the selected record types
+fn parse_with_filter() {
+    let graph: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with_filter("data/testGraph_overlap.gfa", &['S', 'L']).unwrap();
+    assert_eq!(graph.segments.len(), 3);
+    assert_eq!(graph.links.len(), 3);
+    assert!(graph.paths.is_empty());
+    assert!(graph.containment.is_empty());
+
+    let graph: Gfa<u32, (), ()> =
+        Gfa::parse_gfa_file_with_filter("data/testGraph_jumps.gfa", &['S', 'J']).unwrap();
+    assert!(graph.links.is_empty());
+    assert_eq!(graph.jump.len(), 3);
+    // The header is kept without being listed
+    assert_eq!(graph.header.version_number, "1.2");
+}
+
+#[test]
+/// Overlaps are not stored, tags still are
+fn skip_overlaps() {
+    let options = ParseOptions {
+        skip_overlaps: true,
+        ..Default::default()
+    };
+    let graph: Gfa<u32, SeqIndex, SeqIndex> =
+        Gfa::parse_gfa_file_with("data/testGraph_overlap.gfa", &options).unwrap();
+    let full: Gfa<u32, SeqIndex, SeqIndex> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    assert!(graph.get_sequence().len() < full.get_sequence().len());
+    assert_eq!(graph.links[0].overlap.get_string(graph.get_sequence()), "");
+    assert_eq!(graph.paths[0].overlap.get_string(graph.get_sequence()), "");
+    assert_eq!(
+        full.paths[0].overlap.get_string(full.get_sequence()),
+        "2M,2M"
+    );
+    // Without overlaps, paths are joined blunt
+    let path = graph.get_path_by_name("p1").unwrap();
+    assert_eq!(
+        graph.path_sequence_with_overlaps(path).unwrap(),
+        "ACGTACACGGGGTT"
+    );
+}
+
+#[test]
+/// Summary of the graph
+fn display_summary() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    assert_eq!(
+        graph.to_string(),
+        "GFA graph (version 1.0)\nSegments: 3 (14 bp)\nLinks: 3\nPaths: 5\nWalks: 0\nJumps: 0\n\
+         Containments: 2\nEdges: 0\nFragments: 0\nGaps: 0\nNumeric ids: true, compact: true"
+    );
+    let graph: Gfa<u32, (), ()> = Gfa::new();
+    assert!(graph
+        .to_string()
+        .starts_with("GFA graph (version unknown)\nSegments: 0 (0 bp)"));
+}
+
+#[test]
+/// Skip malformed lines and report them
+fn parse_lenient() {
+    let (graph, errors): (Gfa<u32, (), ()>, _) = Gfa::parse_lenient("data/testGraph_malformed.gfa");
+    assert_eq!(graph.segments.len(), 2);
+    assert_eq!(graph.links.len(), 1);
+    assert_eq!(graph.paths.len(), 1);
+    assert!(graph.jump.is_empty());
+    let lines: Vec<usize> = errors.iter().map(|x| x.0).collect();
+    assert_eq!(lines, vec![3, 4, 7, 9, 10]);
+    assert!(matches!(&errors[0].1, GfaError::MalformedLine(x) if x == "invalid id 'x2'"));
+    assert!(matches!(&errors[3].1, GfaError::BadDirection(x) if x == "4"));
+
+    // The strict parser stops at the first error
+    let graph: Result<Gfa<u32, (), ()>, GfaError> =
+        Gfa::parse_gfa_file_with("data/testGraph_malformed.gfa", &ParseOptions::default());
+    assert!(matches!(graph, Err(GfaError::MalformedLine(_))));
+
+    // String ids are fine
+    let (graph, errors): (Gfa<String, (), ()>, _) =
+        Gfa::parse_lenient("data/testGraph_malformed.gfa");
+    assert_eq!(graph.segments.len(), 3);
+    assert_eq!(errors.len(), 4);
+
+    let (_, errors): (Gfa<u32, (), ()>, _) = Gfa::parse_lenient("data/missing.gfa");
+    assert!(matches!(errors[..], [(0, GfaError::Io(_))]));
+
+    // Skipped lines do not add ids to the interner or the graph sequence
+    let (graph, errors): (Gfa<InternedId, (), ()>, _) =
+        Gfa::parse_lenient("data/testGraph_malformed_ids.gfa");
+    let lines: Vec<usize> = errors.iter().map(|x| x.0).collect();
+    assert_eq!(lines, vec![5, 7, 8]);
+    assert!(errors
+        .iter()
+        .all(|x| matches!(&x.1, GfaError::BadDirection(_))));
+    assert_eq!(graph.interner().len(), 2);
+    assert_eq!((graph.links.len(), graph.paths.len()), (1, 1));
+    let (graph, _): (Gfa<SeqIndex, (), ()>, _) =
+        Gfa::parse_lenient("data/testGraph_malformed_ids.gfa");
+    assert!(!graph.get_sequence().contains(['c', 'd', 'f']));
+}
+
+#[test]
+/// Mutable segment lookups
+fn get_segment_mut() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    graph.get_segment_mut(&3).unwrap().length = 100;
+    assert_eq!(graph.get_segment_by_id(&3).length, 100);
+    assert!(graph.get_segment_mut(&1_000_000).is_none());
+
+    let mut graph: Gfa<String, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    graph.get_segment_mut(&"3".to_string()).unwrap().length = 100;
+    assert_eq!(graph.get_segment_by_id(&"3".to_string()).length, 100);
+}
+
+#[test]
+/// Checked slices of a segment sequence
+fn subsequence() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    assert_eq!(graph.subsequence(&1, 1, 4).unwrap(), "CGT");
+    assert_eq!(graph.subsequence(&1, 0, 6).unwrap(), "ACGTAC");
+    assert_eq!(graph.subsequence(&2, 4, 4).unwrap(), "");
+    assert!(matches!(
+        graph.subsequence(&2, 2, 5),
+        Err(GfaError::BadRange(_))
+    ));
+    assert!(matches!(
+        graph.subsequence(&2, 3, 1),
+        Err(GfaError::BadRange(_))
+    ));
+    assert!(matches!(
+        graph.subsequence(&7, 0, 1),
+        Err(GfaError::BadRange(_))
+    ));
+}
+
+#[test]
+/// Path offsets to node offsets
+fn path_coord_to_node() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    // p4: 3+ (GGTT), 2- (CCGT)
+    let path = graph.get_path_by_name("p4").unwrap();
+    assert_eq!(graph.path_coord_to_node(path, 0), Some((3, 0, true)));
+    assert_eq!(graph.path_coord_to_node(path, 3), Some((3, 3, true)));
+    assert_eq!(graph.path_coord_to_node(path, 4), Some((2, 0, false)));
+    assert_eq!(graph.path_coord_to_node(path, 7), Some((2, 3, false)));
+    assert_eq!(graph.path_coord_to_node(path, 8), None);
+}
+
+#[test]
+/// Path position index gives the same nodes as the linear lookup
+fn path_position_index() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let path = &graph.paths[0];
+    let index = graph.build_path_position_index(&path.name).unwrap();
+    assert_eq!(index.len(), graph.path_sequence(path).len());
+    for offset in (0..index.len() + 10).step_by(997) {
+        assert_eq!(index.lookup(offset), graph.path_coord_to_node(path, offset));
+    }
+    assert_eq!(index.node_start(0), Some(0));
+    assert!(graph.build_path_position_index("missing").is_none());
+}
+
+#[test]
+/// Depth tags of the segments
+fn segment_depths() {
+    let graph: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_opt.gfa");
+    assert_eq!(graph.segment_depths("DP"), vec![(2, 2.5)]);
+    assert_eq!(graph.segment_depths("LN"), vec![(1, 4.0)]);
+    assert!(graph.segment_depths("dp").is_empty());
+
+    // Optional fields are not stored
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_opt.gfa");
+    assert!(graph.segment_depths("DP").is_empty());
+
+    // DP is not the first tag, all tags are stored
+    let graph: Gfa<u32, SeqIndex, ()> = Gfa::parse_gfa_file("data/testGraph_depth.gfa");
+    assert_eq!(graph.segment_depths("DP"), vec![(1, 2.5), (2, 3.0)]);
+    assert_eq!(
+        graph.segments[0].opt.get_string(graph.get_sequence()),
+        "LN:i:4\tDP:f:2.5"
+    );
+}
+
+#[test]
+/// String ids to dense numeric ids
+fn to_numeric() {
+    let graph: Gfa<SeqIndex, (), SeqIndex> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    let (numeric, mapping) = graph.to_numeric();
+    assert_eq!(mapping["1a"], 1);
+    assert_eq!(mapping["9"], 8);
+    // "1" is only referenced, it is not a segment
+    assert_eq!(mapping["1"], 9);
+    assert!(numeric.is_compact());
+    assert_eq!(numeric.get_sequence_by_id(&8), "AAAAAAAAAA");
+    assert_eq!((numeric.links[0].from, numeric.links[0].to), (9, 2));
+    assert_eq!(numeric.paths[3].nodes, vec![9, 3, 4, 8]);
+    assert_eq!(
+        numeric.paths[0].overlap.get_string(numeric.get_sequence()),
+        "0M,0M,0M,0M,0M,0M"
+    );
+    let (graph, _) = numeric.to_numeric();
+    assert_eq!(graph.paths[3].nodes, vec![9, 3, 4, 8]);
+
+    let graph: Gfa<String, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let paths: Vec<String> = graph.paths.iter().map(|x| graph.path_sequence(x)).collect();
+    let (numeric, _) = graph.to_numeric();
+    let numeric_paths: Vec<String> = numeric
+        .paths
+        .iter()
+        .map(|x| numeric.path_sequence(x))
+        .collect();
+    assert_eq!(paths, numeric_paths);
+}
+
+#[test]
+/// Equality independent of the record order
+fn semantically_eq() {
+    let graph: Gfa<u32, SeqIndex, SeqIndex> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    let mut other = graph.clone();
+    other.links.reverse();
+    other.paths.reverse();
+    // 1+ -> 2+ is the same link as 2- -> 1-
+    let link = &mut other.links[2];
+    assert_eq!((link.from, link.to), (1, 2));
+    (link.from, link.from_dir, link.to, link.to_dir) = (2, false, 1, false);
+    assert_ne!(graph, other);
+    assert!(graph.semantically_eq(&other));
+
+    other.paths[0].dir[0] = false;
+    assert!(!graph.semantically_eq(&other));
+
+    // Same graph with ids stored in the graph sequence
+    let graph: Gfa<SeqIndex, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let mut other: Gfa<SeqIndex, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    other.links.swap(0, 1);
+    assert!(graph.semantically_eq(&other));
+}
+
+#[test]
+/// Canonical record order
+fn canonicalize() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let mut other = graph.clone();
+    other.links.reverse();
+    other.paths.reverse();
+    other.walk.reverse();
+    graph.canonicalize();
+    other.canonicalize();
+    assert_eq!(graph, other);
+    assert!(graph.paths.windows(2).all(|x| x[0].name <= x[1].name));
+}
+
+#[test]
+/// Custom segment order, lookups still work
+fn sort_segments_by() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let mut sorted = graph.clone();
+    sorted
+        .sort_segments_by(|x| std::cmp::Reverse(x.length))
+        .unwrap();
+    assert!(sorted
+        .segments
+        .windows(2)
+        .all(|x| x[0].length >= x[1].length));
+    for segment in graph.segments.iter() {
+        assert_eq!(
+            sorted.get_sequence_by_id(&segment.id),
+            graph.get_sequence_by_id(&segment.id)
+        );
+        assert_eq!(
+            sorted.segments[sorted.node_index(&segment.id).unwrap()].id,
+            segment.id
+        );
+    }
+    assert_eq!(sorted.is_compact(), graph.is_compact());
+    assert_eq!(sorted.get_index_high(), graph.get_index_high());
+    let (mut sources, mut sinks) = sorted.tips();
+    sources.sort_unstable();
+    sinks.sort_unstable();
+    assert_eq!((sources, sinks), graph.tips());
+    let ids = |g: &Gfa<u32, (), ()>| -> Vec<u32> {
+        g.file_order().iter().map(|&x| g.segments[x].id).collect()
+    };
+    assert_eq!(ids(&sorted), ids(&graph));
+
+    // Ids without a numeric index are not supported, the graph stays as it is
+    let mut graph: Gfa<SeqIndex, (), ()> = Gfa::parse_gfa_file("data/size5.gfa");
+    let before = graph.clone();
+    assert!(matches!(
+        graph.sort_segments_by(|x| std::cmp::Reverse(x.length)),
+        Err(GfaError::NonNumericIds)
+    ));
+    assert_eq!(graph, before);
+}
+
+#[test]
+/// Segment views with their own sequence
+fn segment_view() {
+    let graph: Gfa<SeqIndex, (), ()> = Gfa::parse_gfa_file("data/testGraph_non-num.gfa");
+    let id = graph.segments[1].id.clone();
+    let view = graph.segment_view(&id).unwrap();
+    assert_eq!(graph.original_id(view.id), "2");
+    assert_eq!(view.sequence, "CCCCC");
+    assert_eq!(view.length, 5);
+
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    assert_eq!(graph.segment_view(&3).unwrap().sequence, "GGTT");
+    assert!(graph.segment_view(&4).is_none());
+}
+
+#[test]
+#[cfg(feature = "bitset")]
+/// Neighbourhoods as bitsets
+fn neighbor_bitset() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_overlap.gfa");
+    graph.add_link(1, true, 1, false, (), ());
+    for _ in 0..2 {
+        let ones = |id: u32| graph.neighbor_bitset(&id).ones().collect::<Vec<usize>>();
+        assert_eq!(ones(1), vec![0, 1]);
+        assert_eq!(ones(2), vec![0, 2]);
+        assert_eq!(ones(3), vec![1]);
+        assert!(graph.neighbor_bitset(&4).is_clear());
+        assert_eq!(graph.neighbor_bitset(&1).len(), 3);
+        graph.build_adjacency();
+    }
+}
+
+#[test]
+/// Shortest paths by number of links
+fn shortest_path() {
+    let mut graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    assert_eq!(graph.shortest_path(&1, &9), Some(vec![1, 2, 4, 9]));
+    // Reverse strand: 9- -> 4- -> 2- -> 1-
+    assert_eq!(graph.shortest_path(&9, &1), Some(vec![9, 4, 2, 1]));
+    assert_eq!(graph.shortest_path(&7, &9), Some(vec![7, 8, 9]));
+    assert_eq!(graph.shortest_path(&3, &3), Some(vec![3]));
+    assert_eq!(graph.shortest_path(&1, &6), None);
+
+    assert_eq!(graph.shortest_path_limited(&1, &8, 2), None);
+    assert_eq!(
+        graph.shortest_path_limited(&1, &8, 3),
+        Some(vec![1, 2, 5, 8])
+    );
+
+    graph.add_segment(10, "A", ());
+    graph.build_adjacency();
+    assert_eq!(graph.shortest_path(&1, &10), None);
+    assert_eq!(graph.shortest_path(&1, &9), Some(vec![1, 2, 4, 9]));
+}
+
+#[test]
+/// Shortest paths by sequence length
+fn shortest_path_bp() {
+    let graph: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_complex.gfa");
+    // 1 -> 3 -> 4 (1 bp) is shorter than 1 -> 2 -> 4 (5 bp)
+    assert_eq!(graph.shortest_path_bp(&1, &4), Some((vec![1, 3, 4], 21)));
+    assert_eq!(graph.shortest_path_bp(&4, &1), Some((vec![4, 3, 1], 21)));
+    assert_eq!(graph.shortest_path_bp(&5, &8), Some((vec![5, 8], 20)));
+    assert_eq!(graph.shortest_path_bp(&2, &2), Some((vec![2], 5)));
+    assert_eq!(graph.shortest_path_bp(&9, &7), Some((vec![9, 8, 7], 21)));
+    assert_eq!(graph.shortest_path_bp(&1, &6), None);
+    assert_eq!(graph.shortest_path_bp(&4, &5), None);
+}
+
+#[test]
+/// Query header tags by name
+fn header_get() {
+    let gfa: Gfa<u32, (), ()> = Gfa::parse_gfa_file("data/testGraph_header_rs.gfa");
+    assert_eq!(gfa.header.get("RS"), Some("HG002,HG003"));
+    assert_eq!(gfa.header.get("CL"), Some("tool"));
+    assert_eq!(gfa.header.get("VN"), Some("1.0"));
+    assert_eq!(gfa.header.get("XX"), None);
+    assert_eq!(Gfa::<u32, (), ()>::new().header.get("VN"), None);
+}